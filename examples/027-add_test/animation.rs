@@ -0,0 +1,166 @@
+use bevy::prelude::*;
+
+use crate::GameSet;
+
+pub struct AnimationPlugin;
+
+impl Plugin for AnimationPlugin {
+    fn build(&self, app: &mut App) {
+        // Despawning stones are part of the gameplay, so they are animated in fixed ticks as well
+        app.add_systems(FixedUpdate, animate_sprites.in_set(GameSet::Cleanup));
+    }
+}
+
+// What happens when an animation that is played once reaches its last frame
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum OnFinish {
+    // Keep showing the last frame
+    #[cfg_attr(not(test), expect(dead_code))] // Only used by the tests so far
+    Stop,
+    Despawn,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum AnimationMode {
+    Loop,
+    Once(OnFinish),
+}
+
+// Steps through the frames `first..=last` of the texture atlas of a `Sprite`.
+// Sprites without a texture atlas, like placeholders, still finish the animation in time.
+#[derive(Component)]
+pub struct SpriteAnimation {
+    first: usize,
+    last: usize,
+    frame: usize,
+    mode: AnimationMode,
+    timer: Timer,
+}
+
+impl SpriteAnimation {
+    #[cfg_attr(not(test), expect(dead_code))] // Only used by the tests so far
+    pub fn looping(first: usize, last: usize, fps: f32) -> Self {
+        Self::new(first, last, fps, AnimationMode::Loop)
+    }
+
+    pub fn once(first: usize, last: usize, fps: f32, on_finish: OnFinish) -> Self {
+        Self::new(first, last, fps, AnimationMode::Once(on_finish))
+    }
+
+    fn new(first: usize, last: usize, fps: f32, mode: AnimationMode) -> Self {
+        Self {
+            first,
+            last,
+            frame: first,
+            mode,
+            timer: Timer::from_seconds(1.0 / fps, TimerMode::Repeating),
+        }
+    }
+}
+
+fn animate_sprites(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut sprites: Query<(Entity, &mut Sprite, &mut SpriteAnimation)>,
+) {
+    for (entity, mut sprite, mut animation) in &mut sprites {
+        animation.timer.tick(time.delta());
+
+        // Skip frames if the game runs slower than the animation
+        for _ in 0..animation.timer.times_finished_this_tick() {
+            if animation.frame < animation.last {
+                animation.frame += 1;
+            } else {
+                match animation.mode {
+                    AnimationMode::Loop => animation.frame = animation.first,
+                    AnimationMode::Once(OnFinish::Stop) => {
+                        commands.entity(entity).remove::<SpriteAnimation>();
+                        break;
+                    }
+                    AnimationMode::Once(OnFinish::Despawn) => {
+                        commands.entity(entity).despawn();
+                        break;
+                    }
+                }
+            }
+        }
+
+        if let Some(atlas) = &mut sprite.texture_atlas {
+            atlas.index = animation.frame;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::time::TimeUpdateStrategy;
+
+    use super::*;
+    use crate::testing::test_app;
+
+    fn spawn_animated(app: &mut App, animation: SpriteAnimation) -> Entity {
+        let sprite = Sprite {
+            texture_atlas: Some(TextureAtlas::default()),
+            ..default()
+        };
+
+        app.world_mut().spawn((sprite, animation)).id()
+    }
+
+    fn frame(app: &App, entity: Entity) -> usize {
+        let sprite = app.world().get::<Sprite>(entity).unwrap();
+        sprite.texture_atlas.as_ref().unwrap().index
+    }
+
+    fn animation_app() -> App {
+        let mut app = test_app();
+
+        app.add_plugins(AnimationPlugin)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+                250,
+            )));
+        // The first update only initializes the time
+        app.update();
+
+        app
+    }
+
+    #[test]
+    fn test_looping_animation_wraps_around() {
+        let mut app = animation_app();
+        let entity = spawn_animated(&mut app, SpriteAnimation::looping(0, 2, 4.0));
+
+        let frames: Vec<usize> = (0..4)
+            .map(|_| {
+                app.update();
+                frame(&app, entity)
+            })
+            .collect();
+        assert_eq!(frames, vec![1, 2, 0, 1]);
+    }
+
+    #[test]
+    fn test_animation_played_once_stops_or_despawns() {
+        let mut app = animation_app();
+        let stopping = spawn_animated(&mut app, SpriteAnimation::once(0, 2, 4.0, OnFinish::Stop));
+        let despawning = spawn_animated(
+            &mut app,
+            SpriteAnimation::once(0, 2, 4.0, OnFinish::Despawn),
+        );
+
+        let frames: Vec<usize> = (0..2)
+            .map(|_| {
+                app.update();
+                frame(&app, despawning)
+            })
+            .collect();
+        assert_eq!(frames, vec![1, 2]);
+
+        app.update();
+        assert_eq!(frame(&app, stopping), 2);
+        assert!(app.world().get::<SpriteAnimation>(stopping).is_none());
+        assert!(app.world().get_entity(despawning).is_err());
+    }
+}
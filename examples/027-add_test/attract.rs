@@ -0,0 +1,126 @@
+use bevy::{input::mouse::AccumulatedMouseMotion, prelude::*};
+
+use crate::{
+    autopilot::Autopilot, despawn_screen, rng::GameRng, spawn_playfield, Ball, GameState,
+    OnGameScreen, MAX_Y,
+};
+
+// The time without any input after which the demo starts
+const IDLE_SECONDS: f32 = 10.0;
+
+pub struct AttractModePlugin;
+
+impl Plugin for AttractModePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_sub_state::<TitleMode>()
+            .init_resource::<IdleTimer>()
+            .add_systems(OnEnter(TitleMode::Static), reset_idle_timer)
+            .add_systems(OnEnter(TitleMode::Demo), setup_demo)
+            .add_systems(OnExit(TitleMode::Demo), despawn_screen::<OnGameScreen>)
+            .add_systems(
+                Update,
+                (reset_idle_timer.run_if(any_input), start_demo)
+                    .chain()
+                    .run_if(in_state(TitleMode::Static)),
+            )
+            .add_systems(
+                Update,
+                stop_demo
+                    .run_if(in_state(TitleMode::Demo))
+                    .run_if(any_input.or_else(ball_lost)),
+            );
+    }
+}
+
+// While idle, the title screen shows a demo game played by the AI behind the title text
+#[derive(SubStates, Clone, Copy, Default, Eq, PartialEq, Debug, Hash)]
+#[source(GameState = GameState::Title)]
+pub enum TitleMode {
+    #[default]
+    Static,
+    Demo,
+}
+
+#[derive(Resource)]
+struct IdleTimer(Timer);
+
+impl Default for IdleTimer {
+    fn default() -> Self {
+        Self(Timer::from_seconds(IDLE_SECONDS, TimerMode::Once))
+    }
+}
+
+fn any_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    mouse_motion: Res<AccumulatedMouseMotion>,
+) -> bool {
+    keys.get_just_pressed().next().is_some()
+        || mouse_buttons.get_just_pressed().next().is_some()
+        || mouse_motion.delta != Vec2::ZERO
+}
+
+fn ball_lost(balls: Query<&Transform, With<Ball>>) -> bool {
+    balls.iter().any(|ball| ball.translation.y < -MAX_Y / 2.0)
+}
+
+fn reset_idle_timer(mut idle_timer: ResMut<IdleTimer>) {
+    idle_timer.0.reset();
+}
+
+fn start_demo(
+    time: Res<Time>,
+    mut idle_timer: ResMut<IdleTimer>,
+    mut title_mode: ResMut<NextState<TitleMode>>,
+) {
+    idle_timer.0.tick(time.delta());
+    if idle_timer.0.is_finished() {
+        title_mode.set(TitleMode::Demo);
+    }
+}
+
+// The demo does not touch `Score`, because the scoring systems only run during a game
+fn setup_demo(mut commands: Commands, mut rng: ResMut<GameRng>) {
+    spawn_playfield(&mut commands, &mut rng, 0, Some(Autopilot::default()));
+}
+
+fn stop_demo(mut title_mode: ResMut<NextState<TitleMode>>) {
+    title_mode.set(TitleMode::Static);
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::input::mouse::MouseMotion;
+
+    use super::*;
+    use crate::{headless::headless_app, Score};
+
+    #[test]
+    fn test_demo_starts_when_idle_and_keeps_score() {
+        let mut app = headless_app();
+        app.world_mut()
+            .resource_mut::<NextState<GameState>>()
+            .set(GameState::Title);
+        app.world_mut().resource_mut::<Score>().0 = 1234;
+
+        for _ in 0..(IDLE_SECONDS * 60.0) as u32 + 5 * 60 {
+            app.update();
+        }
+
+        assert_eq!(*app.world().resource::<State<TitleMode>>(), TitleMode::Demo);
+        assert_eq!(app.world().resource::<Score>().0, 1234);
+
+        app.world_mut().write_message(MouseMotion {
+            delta: Vec2::new(5.0, 0.0),
+        });
+        app.update();
+        app.update();
+
+        assert_eq!(
+            *app.world().resource::<State<TitleMode>>(),
+            TitleMode::Static
+        );
+        let mut balls = app.world_mut().query::<&Ball>();
+        assert_eq!(balls.iter(app.world()).count(), 0);
+    }
+}
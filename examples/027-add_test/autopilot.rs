@@ -0,0 +1,159 @@
+use bevy::prelude::*;
+
+use crate::{
+    Ball, GameSet, BALL_RADIUS, BAT_LEFT_BORDER, BAT_RIGHT_BORDER, BAT_SIZE, MAX_X, WALL_THICKNESS,
+};
+
+// The AI cannot move the bat faster than a player with a mouse
+const BAT_MAX_SPEED: f32 = 1800.0;
+// The range in which the center of the ball can move between the side walls
+const BALL_MIN_X: f32 = -MAX_X / 2.0 + WALL_THICKNESS / 2.0 + BALL_RADIUS;
+const BALL_MAX_X: f32 = -BALL_MIN_X;
+
+pub struct AutopilotPlugin;
+
+impl Plugin for AutopilotPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(FixedUpdate, steer_bat.in_set(GameSet::Input));
+    }
+}
+
+// Moves the bat to the predicted landing point of the next descending ball
+#[derive(Component)]
+pub struct Autopilot {
+    // Seconds between two decisions, simulating the reaction time of a player
+    reaction_delay: f32,
+    // The maximum distance between the predicted and the aimed landing point
+    error: f32,
+    since_decision: f32,
+    decisions: u32,
+    target: Option<f32>,
+}
+
+impl Autopilot {
+    pub fn new(reaction_delay: f32, error: f32) -> Self {
+        Self {
+            reaction_delay,
+            error,
+            since_decision: 0.0,
+            decisions: 0,
+            target: None,
+        }
+    }
+
+    // Varies between -1 and 1 from one decision to the next
+    fn jitter(&self) -> f32 {
+        // Multiples of the golden angle are spread evenly
+        (self.decisions as f32 * 2.399_963).sin()
+    }
+}
+
+impl Default for Autopilot {
+    fn default() -> Self {
+        Self::new(0.15, BAT_SIZE.x / 4.0)
+    }
+}
+
+fn steer_bat(
+    time: Res<Time>,
+    balls: Query<(&Ball, &Transform)>,
+    mut bats: Query<(&mut Autopilot, &mut Transform), Without<Ball>>,
+) {
+    for (mut autopilot, mut bat) in &mut bats {
+        autopilot.since_decision += time.delta_secs();
+
+        if autopilot.since_decision >= autopilot.reaction_delay {
+            autopilot.since_decision = 0.0;
+            autopilot.decisions += 1;
+
+            // The height at which the ball touches the top of the bat
+            let intercept_y = bat.translation.y + BAT_SIZE.y / 2.0 + BALL_RADIUS;
+            let error = autopilot.error * autopilot.jitter();
+
+            autopilot.target = balls
+                .iter()
+                .filter(|(ball, transform)| {
+                    ball.velocity.y < 0.0 && transform.translation.y >= intercept_y
+                })
+                .map(|(ball, transform)| {
+                    let position = transform.translation.truncate();
+                    let time = (position.y - intercept_y) / -ball.velocity.y;
+                    (
+                        time,
+                        predict_landing_x(position, ball.velocity, intercept_y),
+                    )
+                })
+                .min_by(|(a, _), (b, _)| a.total_cmp(b))
+                .map(|(_, x)| x + error);
+        }
+
+        if let Some(target) = autopilot.target {
+            let max_step = BAT_MAX_SPEED * time.delta_secs();
+            bat.translation.x += (target - bat.translation.x).clamp(-max_step, max_step);
+            bat.translation.x = bat.translation.x.clamp(BAT_LEFT_BORDER, BAT_RIGHT_BORDER);
+        }
+    }
+}
+
+// Returns the x coordinate at which a ball moving downwards reaches `target_y`,
+// taking reflections at the side walls into account
+fn predict_landing_x(position: Vec2, velocity: Vec2, target_y: f32) -> f32 {
+    let time = (position.y - target_y) / -velocity.y;
+    let x = position.x + velocity.x * time;
+
+    // Every reflection mirrors the path, so fold it back into the space between the walls
+    let width = BALL_MAX_X - BALL_MIN_X;
+    let offset = (x - BALL_MIN_X).rem_euclid(2.0 * width);
+    if offset > width {
+        BALL_MAX_X - (offset - width)
+    } else {
+        BALL_MIN_X + offset
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{headless::headless_app, Bat, GameState, Score};
+
+    #[test]
+    fn test_landing_point_without_reflection() {
+        let x = predict_landing_x(Vec2::new(0.0, 100.0), Vec2::new(100.0, -100.0), 0.0);
+        assert_eq!(x, 100.0);
+    }
+
+    #[test]
+    fn test_landing_point_with_reflections() {
+        // Hits the right wall after 100 units and travels back 50 units
+        let x = predict_landing_x(
+            Vec2::new(BALL_MAX_X - 100.0, 150.0),
+            Vec2::new(1.0, -1.0),
+            0.0,
+        );
+        assert!((x - (BALL_MAX_X - 50.0)).abs() < 0.001);
+
+        // Bounces off both walls
+        let width = BALL_MAX_X - BALL_MIN_X;
+        let x = predict_landing_x(Vec2::new(0.0, width * 2.0), Vec2::new(-1.0, -1.0), 0.0);
+        assert!(x.abs() < 0.001);
+    }
+
+    #[test]
+    fn test_autopilot_keeps_the_ball_in_play() {
+        let mut app = headless_app();
+
+        let mut bats = app.world_mut().query_filtered::<Entity, With<Bat>>();
+        let bat = bats.single(app.world()).unwrap();
+        app.world_mut()
+            .entity_mut(bat)
+            .insert(Autopilot::new(0.0, 0.0));
+
+        // One minute of play
+        for _ in 0..60 * 60 {
+            app.update();
+        }
+
+        assert_eq!(*app.world().resource::<State<GameState>>(), GameState::Game);
+        assert!(app.world().resource::<Score>().0 > 0);
+    }
+}
@@ -0,0 +1,207 @@
+use bevy::prelude::*;
+
+use crate::{
+    check_for_collisions, hit_stone, GameSet, OnGameScreen, Playing, Stone, StoneDestroyed,
+};
+
+// Reaches the neighbouring stones of the classic wall, including the diagonal ones
+const EXPLOSION_RADIUS: f32 = 110.0;
+const FUSE_DURATION: f32 = 0.15;
+
+// Explosive stones damage all stones around them shortly after they are destroyed
+pub struct ExplosionPlugin;
+
+impl Plugin for ExplosionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_observer(light_fuse).add_systems(
+            FixedUpdate,
+            detonate
+                .in_set(GameSet::Resolve)
+                .after(check_for_collisions)
+                .run_if(in_state(Playing)),
+        );
+    }
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Explosive;
+
+// The explosion of a destroyed stone, which outlives the despawning stone itself
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Fuse {
+    pub position: Vec2,
+    pub timer: Timer,
+}
+
+pub struct SpawnFuse {
+    pub position: Vec2,
+    pub duration: f32,
+}
+
+impl Command for SpawnFuse {
+    type Out = ();
+
+    fn apply(self, world: &mut World) {
+        world.spawn((
+            Fuse {
+                position: self.position,
+                timer: Timer::from_seconds(self.duration, TimerMode::Once),
+            },
+            OnGameScreen,
+        ));
+    }
+}
+
+fn light_fuse(
+    destroyed: On<StoneDestroyed>,
+    mut commands: Commands,
+    stones: Query<&Transform, With<Explosive>>,
+) {
+    if let Ok(transform) = stones.get(destroyed.entity) {
+        commands.queue(SpawnFuse {
+            position: transform.translation.truncate(),
+            duration: FUSE_DURATION,
+        });
+    }
+}
+
+// Every stone in reach loses a hit point like a hit by the ball. The stones destroyed by an
+// explosion are scored, add to the combo and light the next fuse like any other destroyed stone.
+fn detonate(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut fuses: Query<(Entity, &mut Fuse)>,
+    mut stones: Query<(Entity, &Transform, &mut Stone)>,
+) {
+    let mut explosions = Vec::new();
+    for (entity, mut fuse) in &mut fuses {
+        fuse.timer.tick(time.delta());
+        if fuse.timer.is_finished() {
+            explosions.push(fuse.position);
+            commands.entity(entity).despawn();
+        }
+    }
+
+    // Explosions in the same tick, and the stones they reach, are handled from left to right
+    // and from bottom to top, so that chain reactions do not depend on the order of entities
+    explosions.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    let mut stones: Vec<_> = stones
+        .iter_mut()
+        .filter(|(_, _, stone)| stone.hit_points > 0)
+        .map(|(entity, transform, stone)| (entity, transform.translation.truncate(), stone))
+        .collect();
+    stones.sort_by(|a, b| a.1.x.total_cmp(&b.1.x).then(a.1.y.total_cmp(&b.1.y)));
+
+    for center in explosions {
+        for (entity, position, stone) in &mut stones {
+            if stone.hit_points == 0 || position.distance(center) > EXPLOSION_RADIUS {
+                continue;
+            }
+
+            hit_stone(&mut commands, *entity, stone);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        check_for_collisions, destroy_stone, extend_combo, handle_score,
+        level::StoneKind,
+        logic::{reveal_hidden_stones, Hidden},
+        testing::{spawn_ball_below, test_app},
+        Combo, Destroyed, Score, SpawnStone, MARGIN, STONE_SIZE,
+    };
+
+    #[test]
+    fn test_explosions_destroy_neighbours_in_a_chain() {
+        let mut app = test_app();
+
+        app.add_observer(destroy_stone)
+            .add_observer(light_fuse)
+            .add_observer(handle_score)
+            .add_observer(extend_combo)
+            .add_systems(Update, (check_for_collisions, detonate).chain());
+
+        // A row of stones, of which only the first explosive one is hit by the ball
+        let step = STONE_SIZE.x + MARGIN;
+        let layout = [
+            (-step, StoneKind::Tough),
+            (0.0, StoneKind::Explosive),
+            (step, StoneKind::Explosive),
+            (2.0 * step, StoneKind::Normal),
+            (4.0 * step, StoneKind::Normal),
+        ];
+        for (x, kind) in layout {
+            app.world_mut().commands().queue(SpawnStone {
+                x,
+                y: 0.0,
+                hit_points: kind.hit_points(),
+                kind,
+                moving: None,
+            });
+        }
+        app.world_mut().flush();
+        spawn_ball_below(app.world_mut(), Vec2::ZERO);
+
+        for _ in 0..30 {
+            app.update();
+        }
+
+        let world = app.world_mut();
+        let mut stones = world
+            .query_filtered::<(&Transform, &Stone), Without<Destroyed>>()
+            .iter(world)
+            .map(|(transform, stone)| (transform.translation.x, stone.hit_points))
+            .collect::<Vec<_>>();
+        stones.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        // The tough stone survives a single explosion, and the last one is out of reach
+        assert_eq!(stones, [(-step, 1), (4.0 * step, 1)]);
+        // Each destroyed stone is scored once, whether it was hit by the ball or an explosion
+        assert_eq!(world.resource::<Score>().0, 300);
+        assert_eq!(world.resource::<Combo>().0, 3);
+    }
+
+    #[test]
+    fn test_explosion_reveals_hidden_stone() {
+        let mut app = test_app();
+
+        app.add_observer(destroy_stone)
+            .add_observer(light_fuse)
+            .add_systems(
+                Update,
+                (check_for_collisions, detonate, reveal_hidden_stones).chain(),
+            );
+
+        let step = STONE_SIZE.x + MARGIN;
+        for (x, kind) in [(0.0, StoneKind::Explosive), (step, StoneKind::Hidden)] {
+            app.world_mut().commands().queue(SpawnStone {
+                x,
+                y: 0.0,
+                hit_points: kind.hit_points(),
+                kind,
+                moving: None,
+            });
+        }
+        app.world_mut().flush();
+        spawn_ball_below(app.world_mut(), Vec2::ZERO);
+
+        for _ in 0..30 {
+            app.update();
+        }
+
+        // The explosion takes the first hit point, so the stone is no longer hidden
+        let world = app.world_mut();
+        let (stone, visibility) = world
+            .query_filtered::<(&Stone, &Visibility), Without<Destroyed>>()
+            .single(world)
+            .unwrap();
+        assert_eq!(stone.hit_points, 1);
+        assert_eq!(*visibility, Visibility::Inherited);
+        assert_eq!(world.query::<&Hidden>().iter(world).count(), 0);
+    }
+}
@@ -0,0 +1,127 @@
+use bevy::{input::InputPlugin, prelude::*, state::app::StatesPlugin, time::TimeUpdateStrategy};
+
+use crate::{autopilot::Autopilot, rng::NextSeed, Bat, GamePlugin, GameSetup, GameState, Score};
+
+// Stops a simulation in which the ball is never lost
+const MAX_TICKS: u32 = 60 * 60 * 10;
+
+// Creates the game without window, audio or renderer, already in `GameState::Game`.
+// Every call of `App::update` advances the game by exactly one tick of `FixedUpdate`.
+pub fn headless_app() -> App {
+    headless_app_with(|_| {})
+}
+
+// Like `headless_app`, but `configure` can change the app before the game starts
+pub fn headless_app_with(configure: impl FnOnce(&mut App)) -> App {
+    let mut app = App::new();
+
+    app.add_plugins((MinimalPlugins, StatesPlugin, InputPlugin, GamePlugin))
+        .insert_resource(TimeUpdateStrategy::FixedTimesteps(1))
+        .insert_state(GameState::Game);
+    // Without `--seed`, every simulation plays the same game
+    if *app.world().resource::<NextSeed>() == NextSeed::Random {
+        app.insert_resource(NextSeed::Fixed(0));
+    }
+    configure(&mut app);
+
+    // The first update only initializes the time and spawns the game
+    app.update();
+
+    app
+}
+
+// Plays a single game without window until the ball is lost, started with `--headless`.
+// With `--autopilot` the bat is moved by the AI, which makes this a soak test of the game.
+pub fn run() {
+    let mut app = headless_app();
+    let mut ticks = 0;
+
+    if std::env::args().any(|arg| arg == "--autopilot") {
+        let mut bats = app.world_mut().query_filtered::<Entity, With<Bat>>();
+        let bats: Vec<Entity> = bats.iter(app.world()).collect();
+        for bat in bats {
+            app.world_mut().entity_mut(bat).insert(Autopilot::default());
+        }
+    }
+
+    while ticks < MAX_TICKS && *app.world().resource::<State<GameState>>() == GameState::Game {
+        app.update();
+        ticks += 1;
+    }
+
+    println!(
+        "Game over after {} ticks with a score of {} (seed {})",
+        ticks,
+        app.world().resource::<Score>().0,
+        app.world().resource::<GameSetup>().seed
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::schedule::{LogLevel, ScheduleBuildSettings};
+
+    use super::*;
+    use crate::{Ball, Combo, Destroyed, Stone, BALL_RADIUS, BALL_SPEED, BALL_START, STONE_SIZE};
+
+    fn stone_at(app: &mut App, position: Vec2) -> Option<Entity> {
+        app.world_mut()
+            .query_filtered::<(Entity, &Transform), With<Stone>>()
+            .iter(app.world())
+            .find(|(_, transform)| transform.translation.truncate() == position)
+            .map(|(entity, _)| entity)
+    }
+
+    #[test]
+    fn test_ball_launched_diagonally_clears_stone_above() {
+        let mut app = headless_app();
+        let stone = stone_at(&mut app, Vec2::new(516.0, 0.0)).expect("stone should exist");
+
+        // Replace the random launch by one to the upper right, starting again from the bat
+        let mut balls = app.world_mut().query::<(&mut Ball, &mut Transform)>();
+        let (mut ball, mut transform) = balls.single_mut(app.world_mut()).unwrap();
+        ball.velocity = Vec2::new(0.5, 0.5).normalize() * BALL_SPEED;
+        transform.translation = BALL_START.extend(0.0);
+
+        for _ in 0..90 {
+            app.update();
+        }
+
+        assert!(app.world().get_entity(stone).is_err());
+        assert_eq!(app.world().resource::<Score>().0, 100);
+    }
+
+    #[test]
+    fn test_collision_is_handled_in_the_same_tick() {
+        let mut app = headless_app();
+        let stone = stone_at(&mut app, Vec2::new(516.0, 0.0)).expect("stone should exist");
+
+        // The ball will touch the stone from below after it moved in the next tick
+        let mut balls = app.world_mut().query::<(&mut Ball, &mut Transform)>();
+        let (mut ball, mut transform) = balls.single_mut(app.world_mut()).unwrap();
+        ball.velocity = Vec2::new(0.0, BALL_SPEED);
+        transform.translation = Vec3::new(516.0, -STONE_SIZE.y / 2.0 - BALL_RADIUS - 5.0, 0.0);
+
+        app.update();
+
+        assert!(app.world().get::<Destroyed>(stone).is_some());
+        assert_eq!(app.world().resource::<Score>().0, 100);
+        assert_eq!(app.world().resource::<Combo>().0, 1);
+    }
+
+    // The systems that start a game read and write the seed, the score and the playfield, so
+    // their order must not be left to the scheduler
+    #[test]
+    fn test_game_starts_without_ambiguous_systems() {
+        let app = headless_app_with(|app| {
+            app.edit_schedule(OnEnter(GameState::Game), |schedule| {
+                schedule.set_build_settings(ScheduleBuildSettings {
+                    ambiguity_detection: LogLevel::Error,
+                    ..default()
+                });
+            });
+        });
+
+        assert_eq!(*app.world().resource::<State<GameState>>(), GameState::Game);
+    }
+}
@@ -0,0 +1,246 @@
+use bevy::{
+    math::bounding::{Aabb2d, BoundingVolume, IntersectsVolume},
+    prelude::*,
+};
+
+use crate::{
+    check_for_collisions, hit_stone, move_bat, Bat, BatInput, Collider, Collision,
+    CollisionMessage, GameSet, GameState, Obstacle, OnGameScreen, Stone, BAT_SIZE, MAX_Y,
+};
+
+const LASER_DURATION: f32 = 10.0;
+const PROJECTILE_SIZE: Vec2 = Vec2::new(6.0, 24.0);
+// Less than the height of a stone per tick, so that no stone is skipped
+const PROJECTILE_SPEED: f32 = 1200.0;
+const PROJECTILE_COLOR: Color = Color::srgb(1.0, 0.3, 0.3);
+// The projectiles are fired from both ends of the bat
+const CANNON_X: f32 = BAT_SIZE.x / 2.0 - 12.0;
+
+// A bat with the laser power-up fires projectiles at the stones with every click
+pub struct LaserPlugin;
+
+impl Plugin for LaserPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (
+                fire_lasers.in_set(GameSet::Input).after(move_bat),
+                move_projectiles.in_set(GameSet::Physics),
+                // Before all systems that react to the collisions of this tick
+                hit_with_projectiles
+                    .in_set(GameSet::Resolve)
+                    .before(check_for_collisions),
+                wear_out_lasers.in_set(GameSet::Cleanup),
+            )
+                .run_if(in_state(GameState::Game)),
+        );
+    }
+}
+
+// Lets the bat fire until its time is up
+#[derive(Clone, Component, Reflect)]
+#[reflect(Component)]
+pub struct Laser {
+    pub timer: Timer,
+}
+
+impl Default for Laser {
+    fn default() -> Self {
+        Self {
+            timer: Timer::from_seconds(LASER_DURATION, TimerMode::Once),
+        }
+    }
+}
+
+#[derive(Component)]
+struct Projectile;
+
+fn fire_lasers(
+    mut commands: Commands,
+    bat_input: Res<BatInput>,
+    bats: Query<&Transform, (With<Bat>, With<Laser>)>,
+) {
+    if !bat_input.click {
+        return;
+    }
+
+    for bat in &bats {
+        for x in [-CANNON_X, CANNON_X] {
+            let position =
+                bat.translation.truncate() + Vec2::new(x, (BAT_SIZE.y + PROJECTILE_SIZE.y) / 2.0);
+            commands.spawn((
+                Sprite::from_color(PROJECTILE_COLOR, PROJECTILE_SIZE),
+                Transform::from_translation(position.extend(0.0)),
+                Projectile,
+                OnGameScreen,
+            ));
+        }
+    }
+}
+
+fn move_projectiles(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut projectiles: Query<(Entity, &mut Transform), With<Projectile>>,
+) {
+    for (entity, mut transform) in &mut projectiles {
+        transform.translation.y += PROJECTILE_SPEED * time.delta_secs();
+
+        // Only if the top wall was missed somehow
+        if transform.translation.y > MAX_Y / 2.0 {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+// A projectile stops at the first obstacle it hits, and hits it from below like a ball would,
+// so that score, combo, sounds and effects react to it in the same way
+fn hit_with_projectiles(
+    mut commands: Commands,
+    projectiles: Query<(Entity, &Transform), With<Projectile>>,
+    mut obstacles: Query<(Entity, &Transform, &Collider, Option<&mut Stone>)>,
+    mut collision_messages: MessageWriter<CollisionMessage>,
+) {
+    for (projectile, transform) in &projectiles {
+        let bounds = Aabb2d::new(transform.translation.truncate(), PROJECTILE_SIZE / 2.0);
+
+        let first_hit = obstacles
+            .iter()
+            .filter(|(_, _, collider, stone)| {
+                !matches!(collider.obstacle, Obstacle::Bat)
+                    && stone.as_ref().is_none_or(|stone| stone.hit_points > 0)
+            })
+            .map(|(entity, obstacle, collider, _)| (entity, collider.bounds(obstacle)))
+            .filter(|(_, obstacle_bounds)| obstacle_bounds.intersects(&bounds))
+            .min_by(|a, b| a.1.min.y.total_cmp(&b.1.min.y));
+        let Some((entity, obstacle_bounds)) = first_hit else {
+            continue;
+        };
+
+        let (_, _, collider, stone) = obstacles.get_mut(entity).unwrap();
+        collision_messages.write(CollisionMessage {
+            ball: projectile,
+            obstacle_entity: entity,
+            obstacle: collider.obstacle,
+            point: obstacle_bounds.closest_point(bounds.center()),
+            side: Collision::Bottom,
+            impact_speed: PROJECTILE_SPEED,
+        });
+        if let Some(mut stone) = stone {
+            hit_stone(&mut commands, entity, &mut stone);
+        }
+
+        commands.entity(projectile).despawn();
+    }
+}
+
+fn wear_out_lasers(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut lasers: Query<(Entity, &mut Laser)>,
+) {
+    for (entity, mut laser) in &mut lasers {
+        laser.timer.tick(time.delta());
+        if laser.timer.is_finished() {
+            commands.entity(entity).remove::<Laser>();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        destroy_stone, handle_score,
+        level::StoneKind,
+        testing::{click, spawn_bat, test_app},
+        Destroyed, Score, SpawnStone, MARGIN, STONE_SIZE,
+    };
+
+    #[test]
+    fn test_projectiles_hit_the_first_stone_above_the_bat() {
+        let mut app = test_app();
+
+        app.add_observer(destroy_stone)
+            .add_observer(handle_score)
+            .add_systems(
+                Update,
+                (fire_lasers, move_projectiles, hit_with_projectiles).chain(),
+            );
+        let bat = spawn_bat(&mut app);
+
+        // A column of stones above each cannon, with a tough stone at the bottom of the left one
+        for x in [-CANNON_X, CANNON_X] {
+            for row in 0..2 {
+                let kind = if x < 0.0 && row == 0 {
+                    StoneKind::Tough
+                } else {
+                    StoneKind::Normal
+                };
+                app.world_mut().commands().queue(SpawnStone {
+                    x,
+                    y: row as f32 * (STONE_SIZE.y + MARGIN),
+                    hit_points: kind.hit_points(),
+                    kind,
+                    moving: None,
+                });
+            }
+        }
+        app.world_mut().flush();
+
+        // Without the power-up, clicking does nothing
+        click(&mut app);
+        let world = app.world_mut();
+        assert_eq!(world.query::<&Projectile>().iter(world).count(), 0);
+
+        world.entity_mut(bat).insert(Laser::default());
+        click(&mut app);
+        for _ in 0..60 {
+            app.update();
+        }
+
+        let world = app.world_mut();
+        assert_eq!(world.query::<&Projectile>().iter(world).count(), 0);
+        let mut stones: Vec<_> = world
+            .query_filtered::<(&Transform, &Stone), Without<Destroyed>>()
+            .iter(world)
+            .map(|(transform, stone)| {
+                (
+                    transform.translation.x,
+                    transform.translation.y,
+                    stone.hit_points,
+                )
+            })
+            .collect();
+        stones.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1)));
+        assert_eq!(
+            stones,
+            [
+                (-CANNON_X, 0.0, 1),
+                (-CANNON_X, STONE_SIZE.y + MARGIN, 1),
+                (CANNON_X, STONE_SIZE.y + MARGIN, 1),
+            ]
+        );
+        // Only the stone that was destroyed is scored
+        assert_eq!(world.resource::<Score>().0, 100);
+    }
+
+    #[test]
+    fn test_laser_wears_out() {
+        let mut app = test_app();
+
+        app.add_systems(Update, (fire_lasers, wear_out_lasers).chain());
+        let bat = spawn_bat(&mut app);
+        app.world_mut().entity_mut(bat).insert(Laser::default());
+
+        // 16 ms per update
+        for _ in 0..(LASER_DURATION * 1000.0 / 16.0) as usize + 1 {
+            app.update();
+        }
+
+        click(&mut app);
+        let world = app.world_mut();
+        assert!(world.get::<Laser>(bat).is_none());
+        assert_eq!(world.query::<&Projectile>().iter(world).count(), 0);
+    }
+}
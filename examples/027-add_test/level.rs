@@ -0,0 +1,611 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+
+use crate::{
+    argument_value, explosion::Fuse, launch_ball, moving::ObstaclePath, rng::GameRng, spawn_level,
+    Ball, GameSetup, GameState, Stone, StoneDestroyed, MARGIN, MAX_X, MAX_Y, STONE_SIZE,
+    WALL_THICKNESS,
+};
+
+// The area in which stones are placed, between the walls and above the middle of the playfield
+const STONE_AREA: Rect = Rect {
+    min: Vec2::new(
+        -MAX_X / 2.0 + WALL_THICKNESS / 2.0 + MARGIN,
+        -STONE_SIZE.y / 2.0,
+    ),
+    max: Vec2::new(
+        MAX_X / 2.0 - WALL_THICKNESS / 2.0 - MARGIN,
+        MAX_Y / 2.0 - WALL_THICKNESS / 2.0 - MARGIN,
+    ),
+};
+// Generated levels start with this many rows and get one more row per difficulty
+const MIN_ROWS: u32 = 4;
+// Higher levels are generated like this one, so that moving obstacles stay catchable
+const MAX_DIFFICULTY: u32 = 20;
+// Moving obstacles are placed between the stones and the bat, so that they never meet the stones
+const BARRIER_SIZE: Vec2 = Vec2::new(STONE_SIZE.x * 2.0, WALL_THICKNESS);
+const BARRIER_Y: f32 = -250.0;
+const ORBIT_Y: f32 = -150.0;
+const ORBIT_RADIUS: f32 = 60.0;
+// Gates and switches are placed between the stones and the orbiting stones
+const GATE_Y: f32 = -45.0;
+const GATE_X: f32 = 300.0;
+const GATE_SIZE: Vec2 = Vec2::new(300.0, WALL_THICKNESS);
+const SWITCH_X: f32 = 800.0;
+const LOCK_DIFFICULTY: u32 = 5;
+const GATE_DIFFICULTY: u32 = 6;
+
+// Selects the level with `--level <n>`. Level 0 is the classic wall of stones,
+// all others are generated from the seed of the game with the level as difficulty.
+// Once all stones are destroyed, the game continues with the next level.
+pub struct LevelPlugin;
+
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        if let Some(level) = argument_value("--level") {
+            match level.parse() {
+                Ok(level) => {
+                    app.insert_resource(GameSetup { level, ..default() });
+                }
+                Err(error) => error!("Invalid level {level}: {error}"),
+            }
+        }
+
+        app.add_observer(clear_level);
+    }
+}
+
+fn clear_level(
+    destroyed: On<StoneDestroyed>,
+    mut commands: Commands,
+    game_state: Res<State<GameState>>,
+    stones: Query<&Stone>,
+) {
+    // The demo on the title screen does not change the level of the game.
+    // Other stones can be destroyed in the same tick, so their hit points are checked instead
+    // of whether they are already `Destroyed`.
+    if *game_state != GameState::Game || stones.iter().any(|stone| stone.hit_points > 0) {
+        return;
+    }
+
+    info!("Cleared the level with stone {}", destroyed.entity);
+    commands.run_system_cached(start_next_level);
+}
+
+// The obstacles of a level besides its stones, which are replaced by those of the next level
+#[derive(Component)]
+pub struct LevelObstacle;
+
+// The ball is launched again from the bat, and the score is kept.
+// Explosions that are still pending would otherwise hit the stones of the next level.
+fn start_next_level(
+    mut commands: Commands,
+    balls: Query<Entity, With<Ball>>,
+    obstacles: Query<Entity, With<LevelObstacle>>,
+    fuses: Query<Entity, With<Fuse>>,
+    mut game_setup: ResMut<GameSetup>,
+    mut rng: ResMut<GameRng>,
+) {
+    for entity in balls.iter().chain(&obstacles).chain(&fuses) {
+        commands.entity(entity).despawn();
+    }
+
+    game_setup.level = game_setup.level.saturating_add(1);
+    launch_ball(&mut commands, &mut rng);
+    spawn_level(&mut commands, &mut rng, game_setup.level);
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum StoneKind {
+    Normal,
+    // Needs two hits
+    Tough,
+    // Damages the stones around it when it is destroyed
+    Explosive,
+    // Unlocks the locks of the level once all keys are destroyed
+    Key,
+    // Invisible until the first hit, which only reveals it
+    Hidden,
+}
+
+impl StoneKind {
+    pub fn hit_points(self) -> u32 {
+        match self {
+            StoneKind::Normal | StoneKind::Explosive | StoneKind::Key => 1,
+            StoneKind::Tough | StoneKind::Hidden => 2,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct StonePlacement {
+    // Where the stone starts, on its path if it moves
+    pub position: Vec2,
+    pub kind: StoneKind,
+    pub path: Option<ObstaclePath>,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BarrierPlacement {
+    pub size: Vec2,
+    pub path: ObstaclePath,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct GatePlacement {
+    pub position: Vec2,
+    pub size: Vec2,
+    pub open: bool,
+}
+
+#[derive(Default, PartialEq, Debug)]
+pub struct LevelLayout {
+    pub stones: Vec<StonePlacement>,
+    pub barriers: Vec<BarrierPlacement>,
+    // Every switch toggles all gates of the level
+    pub switches: Vec<Vec2>,
+    pub gates: Vec<GatePlacement>,
+    pub locks: Vec<Vec2>,
+}
+
+// The patterns of generated levels, in coordinates from -1 to 1 across the used rows and columns
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Shape {
+    Block,
+    Diamond,
+    Pyramid,
+    Ring,
+    Checkerboard,
+    Stripes,
+}
+
+impl Shape {
+    const ALL: [Shape; 6] = [
+        Shape::Block,
+        Shape::Diamond,
+        Shape::Pyramid,
+        Shape::Ring,
+        Shape::Checkerboard,
+        Shape::Stripes,
+    ];
+
+    fn contains(self, u: f32, v: f32, column: u32, row: u32) -> bool {
+        match self {
+            Shape::Block => true,
+            Shape::Diamond => u.abs() + v.abs() <= 1.0,
+            // Wide at the bottom and narrow at the top
+            Shape::Pyramid => u.abs() <= (1.0 - v) / 2.0 + 0.1,
+            Shape::Ring => (0.35..=1.0).contains(&(u * u + v * v)),
+            Shape::Checkerboard => (column + row).is_multiple_of(2),
+            Shape::Stripes => row.is_multiple_of(2),
+        }
+    }
+}
+
+pub fn level_layout(level: u32, rng: &mut GameRng) -> LevelLayout {
+    match level {
+        0 => classic(),
+        difficulty => generate(rng, difficulty),
+    }
+}
+
+// The hand-authored wall of stones that fills the upper half of the playfield
+fn classic() -> LevelLayout {
+    let mut stones = Vec::new();
+
+    for x in (((-MAX_X / 2.0 + WALL_THICKNESS / 2.0 + MARGIN + STONE_SIZE.x / 2.0 + 3.0) as i32)
+        ..(MAX_X / 2.0) as i32)
+        .step_by((STONE_SIZE.x + MARGIN) as usize)
+    {
+        for y in (0..((MAX_Y / 2.0 - WALL_THICKNESS / 2.0 - MARGIN - STONE_SIZE.y / 2.0) as i32))
+            .step_by((STONE_SIZE.y + MARGIN) as usize)
+        {
+            stones.push(StonePlacement {
+                position: Vec2::new(x as f32, y as f32),
+                kind: StoneKind::Normal,
+                path: None,
+            });
+        }
+    }
+
+    LevelLayout {
+        stones,
+        ..default()
+    }
+}
+
+// Places stones on a grid that fits into `STONE_AREA`, so that they never overlap.
+// Higher difficulties use more rows, leave fewer gaps, contain more tough stones
+// and add explosive and hidden stones, moving obstacles, locks and gates.
+fn generate(rng: &mut GameRng, difficulty: u32) -> LevelLayout {
+    let cell = STONE_SIZE + MARGIN;
+    let max_columns = ((STONE_AREA.width() + MARGIN) / cell.x) as u32;
+    let max_rows = ((STONE_AREA.height() + MARGIN) / cell.y) as u32;
+
+    let difficulty = difficulty.min(MAX_DIFFICULTY);
+    let rows = MIN_ROWS.saturating_add(difficulty).min(max_rows);
+    let density = (0.5 + 0.1 * difficulty as f32).min(1.0);
+    let tough_chance = (0.1 * difficulty as f32).min(0.6);
+    let special_chance = (0.05 * difficulty.saturating_sub(2) as f32).min(0.2);
+    let shape = Shape::ALL[(rng.next_u64() % Shape::ALL.len() as u64) as usize];
+
+    // The grid is centered horizontally, so that mirrored stones are placed symmetrically
+    let width = max_columns as f32 * cell.x - MARGIN;
+    let left = -width / 2.0 + STONE_SIZE.x / 2.0;
+    let bottom = STONE_AREA.min.y + STONE_SIZE.y / 2.0;
+
+    let mut stones = Vec::new();
+    for row in 0..rows {
+        let v = (2 * row + 1) as f32 / rows as f32 - 1.0;
+
+        // Every decision is made for a column on the left and used for its mirror image as well
+        for column in 0..max_columns.div_ceil(2) {
+            let u = (2 * column + 1) as f32 / max_columns as f32 - 1.0;
+            if !shape.contains(u, v, column, row) || !rng.chance(density) {
+                continue;
+            }
+
+            let kind = if rng.chance(tough_chance) {
+                StoneKind::Tough
+            } else if rng.chance(special_chance) {
+                StoneKind::Explosive
+            } else if rng.chance(special_chance) {
+                StoneKind::Hidden
+            } else {
+                StoneKind::Normal
+            };
+
+            let y = bottom + row as f32 * cell.y;
+            let mirrored = max_columns - 1 - column;
+            stones.push(StonePlacement {
+                position: Vec2::new(left + column as f32 * cell.x, y),
+                kind,
+                path: None,
+            });
+            // With an odd number of columns, the middle column is its own mirror image
+            if mirrored != column {
+                stones.push(StonePlacement {
+                    position: Vec2::new(left + mirrored as f32 * cell.x, y),
+                    kind,
+                    path: None,
+                });
+            }
+        }
+    }
+
+    // The top row is locked until the lowest, most central stones are destroyed
+    let mut locks = Vec::new();
+    if difficulty >= LOCK_DIFFICULTY {
+        let top = bottom + (rows - 1) as f32 * cell.y;
+        stones.retain(|stone| {
+            let locked = stone.position.y == top;
+            if locked {
+                locks.push(stone.position);
+            }
+            !locked
+        });
+    }
+
+    // A level without stones would be won immediately
+    if stones.is_empty() {
+        stones.push(StonePlacement {
+            position: Vec2::new(0.0, bottom),
+            kind: StoneKind::Normal,
+            path: None,
+        });
+    }
+
+    let mut barriers = Vec::new();
+    if difficulty >= 2 {
+        barriers.push(BarrierPlacement {
+            size: BARRIER_SIZE,
+            path: ObstaclePath::Patrol {
+                center: Vec2::new(0.0, BARRIER_Y),
+                extent: rng.range(200.0..400.0),
+                speed: 100.0 + 20.0 * difficulty as f32,
+            },
+        });
+    }
+
+    // A pair of stones circling in opposite directions
+    if difficulty >= 4 {
+        let path = ObstaclePath::Orbit {
+            center: Vec2::new(rng.range(300.0..600.0), ORBIT_Y),
+            radius: ORBIT_RADIUS,
+            phase: rng.range(0.0..TAU),
+            angular_speed: 1.0 + 0.1 * difficulty as f32,
+        };
+        for path in [path, path.mirrored()] {
+            stones.push(StonePlacement {
+                position: path.position(0.0),
+                kind: StoneKind::Normal,
+                path: Some(path),
+            });
+        }
+    }
+
+    // Only stones that stay in place become keys, never the orbiting ones
+    if let Some(key) = stones
+        .iter()
+        .filter(|stone| stone.path.is_none())
+        .map(|stone| (stone.position.y, stone.position.x.abs()))
+        .min_by(|a, b| a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1)))
+        .filter(|_| !locks.is_empty())
+    {
+        // Both stones of a mirrored pair become keys
+        for stone in stones.iter_mut().filter(|stone| stone.path.is_none()) {
+            if (stone.position.y - key.0).abs() < 0.001
+                && (stone.position.x.abs() - key.1).abs() < 0.001
+            {
+                stone.kind = StoneKind::Key;
+            }
+        }
+    }
+
+    // Gates between the stones and the moving obstacles, of which one is open at a time
+    let mut switches = Vec::new();
+    let mut gates = Vec::new();
+    if difficulty >= GATE_DIFFICULTY {
+        for (side, open) in [(-1.0, false), (1.0, true)] {
+            switches.push(Vec2::new(side * SWITCH_X, GATE_Y));
+            gates.push(GatePlacement {
+                position: Vec2::new(side * GATE_X, GATE_Y),
+                size: GATE_SIZE,
+                open,
+            });
+        }
+    }
+
+    LevelLayout {
+        stones,
+        barriers,
+        switches,
+        gates,
+        locks,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::bounding::{Aabb2d, IntersectsVolume};
+
+    use super::*;
+    use crate::{
+        explosion::Explosive,
+        headless::{headless_app, headless_app_with},
+        logic::{Gate, Switch},
+        moving::MovingObstacle,
+        Score, WallLocation, BALL_RADIUS, BALL_SPEED, BALL_START, BAT_SIZE,
+    };
+
+    // Moving obstacles are checked with the whole area they cover along their path
+    fn assert_layout_fits(layout: &LevelLayout) {
+        let walls = [WallLocation::Top, WallLocation::Left, WallLocation::Right]
+            .map(|wall| Aabb2d::new(wall.position().truncate(), wall.size().truncate() / 2.0));
+        let bat_top = -MAX_Y / 2.0 + WALL_THICKNESS + MARGIN + BAT_SIZE.y / 2.0;
+
+        let stones = layout.stones.iter().map(|stone| match stone.path {
+            Some(path) => path.bounds(STONE_SIZE),
+            None => {
+                let bounds = Rect::from_center_size(stone.position, STONE_SIZE);
+                assert!(STONE_AREA.contains(bounds.min) && STONE_AREA.contains(bounds.max));
+                bounds
+            }
+        });
+        let barriers = layout
+            .barriers
+            .iter()
+            .map(|barrier| barrier.path.bounds(barrier.size));
+        let switches_and_locks = layout
+            .switches
+            .iter()
+            .chain(&layout.locks)
+            .map(|&position| Rect::from_center_size(position, STONE_SIZE));
+        let gates = layout
+            .gates
+            .iter()
+            .map(|gate| Rect::from_center_size(gate.position, gate.size));
+        let bounds: Vec<_> = stones
+            .chain(barriers)
+            .chain(switches_and_locks)
+            .chain(gates)
+            .map(|rect| Aabb2d::new(rect.center(), rect.half_size()))
+            .collect();
+
+        for (index, obstacle) in bounds.iter().enumerate() {
+            assert!(obstacle.min.y > bat_top);
+            assert!(walls.iter().all(|wall| !wall.intersects(obstacle)));
+            assert!(bounds[index + 1..]
+                .iter()
+                .all(|other| !other.intersects(obstacle)));
+        }
+    }
+
+    #[test]
+    fn test_obstacles_never_overlap_walls_or_each_other() {
+        assert_layout_fits(&classic());
+
+        for seed in 0..50 {
+            for difficulty in 1..=MAX_DIFFICULTY {
+                let layout = generate(&mut GameRng::new(seed), difficulty);
+                assert!(!layout.stones.is_empty());
+                assert_layout_fits(&layout);
+            }
+        }
+    }
+
+    // Only the highest stone that does not move and breaks with one hit remains, and the ball
+    // is about to hit it from below
+    fn destroy_last_stone(app: &mut App) {
+        let world = app.world_mut();
+        let mut stones: Vec<(Entity, Vec3)> = world
+            .query_filtered::<(Entity, &Transform, &Stone), Without<MovingObstacle>>()
+            .iter(world)
+            .filter(|(_, _, stone)| stone.hit_points == 1)
+            .map(|(entity, transform, _)| (entity, transform.translation))
+            .collect();
+        stones.sort_by(|a, b| a.1.y.total_cmp(&b.1.y));
+        let (last, position) = stones.pop().expect("a stone should exist");
+
+        let others: Vec<Entity> = world
+            .query_filtered::<Entity, With<Stone>>()
+            .iter(world)
+            .filter(|&stone| stone != last)
+            .collect();
+        for stone in others {
+            world.despawn(stone);
+        }
+        let (mut ball, mut transform) = world
+            .query::<(&mut Ball, &mut Transform)>()
+            .single_mut(world)
+            .unwrap();
+        ball.velocity = Vec2::new(0.0, BALL_SPEED);
+        transform.translation = position - Vec3::Y * (STONE_SIZE.y / 2.0 + BALL_RADIUS + 5.0);
+
+        app.update();
+    }
+
+    #[test]
+    fn test_next_level_starts_after_last_stone() {
+        let mut app = headless_app();
+
+        destroy_last_stone(&mut app);
+
+        let world = app.world_mut();
+        assert_eq!(world.resource::<GameSetup>().level, 1);
+        assert_eq!(world.resource::<Score>().0, 100);
+        let remaining = world
+            .query::<&Stone>()
+            .iter(world)
+            .filter(|stone| stone.hit_points > 0)
+            .count();
+        assert!(remaining > 0);
+        let ball = world
+            .query_filtered::<&Transform, With<Ball>>()
+            .single(world)
+            .unwrap();
+        assert_eq!(ball.translation.truncate(), BALL_START);
+    }
+
+    // Sums up the hit points of the stones that are left
+    fn hit_points(app: &mut App) -> u32 {
+        let world = app.world_mut();
+        world
+            .query::<&Stone>()
+            .iter(world)
+            .map(|stone| stone.hit_points)
+            .sum()
+    }
+
+    #[test]
+    fn test_explosion_of_last_stone_does_not_reach_next_level() {
+        let mut app = headless_app();
+        let world = app.world_mut();
+        let stones: Vec<Entity> = world
+            .query_filtered::<Entity, With<Stone>>()
+            .iter(world)
+            .collect();
+        for stone in stones {
+            world.entity_mut(stone).insert(Explosive);
+        }
+
+        destroy_last_stone(&mut app);
+
+        assert_eq!(app.world().resource::<GameSetup>().level, 1);
+        let world = app.world_mut();
+        assert_eq!(world.query::<&Fuse>().iter(world).count(), 0);
+        let next_level = hit_points(&mut app);
+        // Longer than the fuse burns, and too short for the ball to reach the stones
+        for _ in 0..15 {
+            app.update();
+        }
+        assert_eq!(hit_points(&mut app), next_level);
+    }
+
+    #[test]
+    fn test_level_counter_stops_at_highest_level() {
+        let mut app = headless_app_with(|app| {
+            app.insert_resource(GameSetup {
+                level: u32::MAX,
+                ..default()
+            });
+        });
+
+        destroy_last_stone(&mut app);
+
+        assert_eq!(app.world().resource::<GameSetup>().level, u32::MAX);
+    }
+
+    #[test]
+    fn test_next_level_replaces_gates_and_switches() {
+        let mut app = headless_app_with(|app| {
+            app.insert_resource(GameSetup {
+                level: GATE_DIFFICULTY,
+                ..default()
+            });
+        });
+
+        destroy_last_stone(&mut app);
+
+        let world = app.world_mut();
+        assert_eq!(world.resource::<GameSetup>().level, GATE_DIFFICULTY + 1);
+        assert_eq!(world.query::<&Switch>().iter(world).count(), 2);
+        let gates: Vec<bool> = world
+            .query::<&Gate>()
+            .iter(world)
+            .map(|gate| gate.open)
+            .collect();
+        assert_eq!(gates.len(), 2);
+        assert_eq!(gates.iter().filter(|&&open| open).count(), 1);
+    }
+
+    #[test]
+    fn test_locked_levels_can_be_unlocked() {
+        let mut locked_levels = 0;
+        for seed in 0..50 {
+            let layout = generate(&mut GameRng::new(seed), GATE_DIFFICULTY);
+            let keys: Vec<_> = layout
+                .stones
+                .iter()
+                .filter(|stone| stone.kind == StoneKind::Key)
+                .collect();
+            // The orbiting stones of these levels never become keys
+            assert!(keys.iter().all(|stone| stone.path.is_none()));
+            // Some shapes leave the top row empty
+            if !layout.locks.is_empty() {
+                locked_levels += 1;
+                assert!(!keys.is_empty());
+            }
+            assert_eq!(layout.switches.len(), 2);
+            assert_eq!(layout.gates.iter().filter(|gate| gate.open).count(), 1);
+        }
+        assert!(locked_levels > 0);
+    }
+
+    #[test]
+    fn test_highest_levels_are_generated_like_the_max_difficulty() {
+        let layout = generate(&mut GameRng::new(0), u32::MAX);
+        assert_layout_fits(&layout);
+        assert_eq!(
+            layout.stones,
+            generate(&mut GameRng::new(0), MAX_DIFFICULTY).stones
+        );
+    }
+
+    #[test]
+    fn test_generated_levels_are_symmetric_and_reproducible() {
+        for seed in 0..20 {
+            let stones = generate(&mut GameRng::new(seed), 5).stones;
+            assert_eq!(generate(&mut GameRng::new(seed), 5).stones, stones);
+
+            for stone in &stones {
+                let mirrored = Vec2::new(-stone.position.x, stone.position.y);
+                assert!(stones
+                    .iter()
+                    .any(|other| (other.position - mirrored).length() < 0.001
+                        && other.kind == stone.kind));
+            }
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use bevy::{
+    asset::{LoadState, UntypedAssetId},
+    prelude::*,
+};
+
+use crate::{despawn_screen, GameState, STONE_ANIMATION_FRAMES, STONE_SIZE};
+
+pub struct LoadingPlugin;
+
+impl Plugin for LoadingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Loading), load_assets)
+            .add_systems(
+                OnExit(GameState::Loading),
+                despawn_screen::<OnLoadingScreen>,
+            )
+            .add_systems(
+                Update,
+                check_loading_progress.run_if(in_state(GameState::Loading)),
+            );
+    }
+}
+
+// All assets of the game, loaded up front so that nothing is loaded on first use
+#[derive(Resource)]
+pub struct GameAssets {
+    pub ball: Handle<Image>,
+    pub bat: Handle<Image>,
+    pub stone: Handle<Image>,
+    // The layout of `stone`, shared by all stones
+    pub stone_layout: Handle<TextureAtlasLayout>,
+    pub font: Handle<Font>,
+    pub bat_sound: Handle<AudioSource>,
+    pub stone_sound: Handle<AudioSource>,
+    pub wall_sound: Handle<AudioSource>,
+    // Assets that could not be loaded and must be replaced by fallbacks
+    failed: Vec<UntypedAssetId>,
+}
+
+impl GameAssets {
+    pub fn is_available(&self, id: impl Into<UntypedAssetId>) -> bool {
+        !self.failed.contains(&id.into())
+    }
+
+    // Falls back to the default font if the font of the game could not be loaded
+    pub fn font_source(&self) -> FontSource {
+        if self.is_available(self.font.id()) {
+            self.font.clone().into()
+        } else {
+            TextFont::default().font
+        }
+    }
+
+    fn untyped_ids(&self) -> [UntypedAssetId; 7] {
+        [
+            self.ball.id().untyped(),
+            self.bat.id().untyped(),
+            self.stone.id().untyped(),
+            self.font.id().untyped(),
+            self.bat_sound.id().untyped(),
+            self.stone_sound.id().untyped(),
+            self.wall_sound.id().untyped(),
+        ]
+    }
+}
+
+#[derive(Component)]
+struct OnLoadingScreen;
+
+#[derive(Component)]
+struct LoadingProgress;
+
+fn load_assets(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+) {
+    let stone_layout = TextureAtlasLayout::from_grid(
+        UVec2::new(STONE_SIZE.x as u32, STONE_SIZE.y as u32), /*tile_size*/
+        STONE_ANIMATION_FRAMES as u32,                        /*columns*/
+        1,                                                    /*rows*/
+        None,                                                 /*padding*/
+        None,                                                 /*offset*/
+    );
+
+    commands.insert_resource(GameAssets {
+        ball: asset_server.load("sprites/ball.png"),
+        bat: asset_server.load("sprites/bat.png"),
+        stone: asset_server.load("sprites/stone-animated.png"),
+        stone_layout: texture_atlas_layouts.add(stone_layout),
+        font: asset_server.load("fonts/AllertaStencil-Regular.ttf"),
+        bat_sound: asset_server.load("sounds/bat.ogg"),
+        stone_sound: asset_server.load("sounds/stone.ogg"),
+        wall_sound: asset_server.load("sounds/wall.ogg"),
+        failed: Vec::new(),
+    });
+
+    // The font of the game is not available yet, so the default font is used
+    commands.spawn((
+        Text2d::new("Loading"),
+        TextFont {
+            font_size: FontSize::Px(64.0),
+            ..default()
+        },
+        TextLayout::justify(Justify::Center),
+        LoadingProgress,
+        OnLoadingScreen,
+    ));
+}
+
+fn check_loading_progress(
+    asset_server: Res<AssetServer>,
+    mut game_assets: ResMut<GameAssets>,
+    mut progress_texts: Query<&mut Text2d, With<LoadingProgress>>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    let ids = game_assets.untyped_ids();
+    let mut finished = 0;
+
+    for id in ids {
+        if asset_server.is_loaded_with_dependencies(id) {
+            finished += 1;
+        } else if let Some(LoadState::Failed(error)) = asset_server.get_load_state(id) {
+            // A missing asset should not prevent the game from starting
+            if game_assets.is_available(id) {
+                error!("Failed to load asset, using a fallback instead: {error}");
+                game_assets.failed.push(id);
+            }
+            finished += 1;
+        }
+    }
+
+    for mut text in &mut progress_texts {
+        text.0 = format!("Loading {}%", finished * 100 / ids.len());
+    }
+
+    if finished == ids.len() {
+        game_state.set(GameState::Title);
+    }
+}
+
+// Returns the image selected by `image` if it is available. Without `GameAssets`, e.g. in tests
+// that only use `MinimalPlugins`, there are no images at all and `None` is returned as well.
+pub fn available_image(
+    world: &World,
+    image: impl FnOnce(&GameAssets) -> &Handle<Image>,
+) -> Option<Handle<Image>> {
+    let Some(game_assets) = world.get_resource::<GameAssets>() else {
+        warn_once!("GameAssets are missing, spawning placeholder sprites instead");
+        return None;
+    };
+
+    let image = image(game_assets);
+    game_assets.is_available(image.id()).then(|| image.clone())
+}
@@ -0,0 +1,311 @@
+use bevy::prelude::*;
+
+use crate::{
+    check_for_collisions,
+    level::{LevelObstacle, StoneKind},
+    Collider, CollisionMessage, GameSet, Obstacle, OnGameScreen, Playing, Stone, StoneDestroyed,
+    STONE_SIZE,
+};
+
+const SWITCH_COLOR: Color = Color::srgb(0.3, 0.85, 0.85);
+const GATE_COLOR: Color = Color::srgb(0.3, 0.6, 0.6);
+const LOCK_COLOR: Color = Color::srgb(0.6, 0.5, 0.2);
+// Open gates are still shown, so that the player knows where they close again
+const OPEN_GATE_ALPHA: f32 = 0.25;
+
+// Switches open and close gates, key stones unlock locks and hidden stones appear when touched.
+// All of them are obstacles like any other, and react to the collisions of the ball.
+pub struct LevelLogicPlugin;
+
+impl Plugin for LevelLogicPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_observer(unlock_locks).add_systems(
+            FixedUpdate,
+            (toggle_gates, reveal_hidden_stones)
+                .in_set(GameSet::Resolve)
+                .after(check_for_collisions)
+                .run_if(in_state(Playing)),
+        );
+    }
+}
+
+// Opens all closed gates and closes all open ones when it is hit
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Switch;
+
+#[derive(Clone, Copy, Component, Reflect)]
+#[reflect(Component)]
+pub struct Gate {
+    pub size: Vec2,
+    pub open: bool,
+}
+
+// An indestructible obstacle that disappears once all key stones are destroyed
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Lock;
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Key;
+
+// A stone that is invisible until it is touched for the first time, which costs a hit point
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Hidden;
+
+pub struct SpawnSwitch {
+    pub position: Vec2,
+}
+
+impl Command for SpawnSwitch {
+    type Out = ();
+
+    fn apply(self, world: &mut World) {
+        world.spawn((
+            Sprite::from_color(SWITCH_COLOR, STONE_SIZE),
+            Transform::from_translation(self.position.extend(0.0)),
+            Collider {
+                size: Some(STONE_SIZE),
+                obstacle: Obstacle::Switch,
+            },
+            Switch,
+            LevelObstacle,
+            OnGameScreen,
+        ));
+    }
+}
+
+pub struct SpawnGate {
+    pub position: Vec2,
+    pub size: Vec2,
+    pub open: bool,
+}
+
+impl Command for SpawnGate {
+    type Out = ();
+
+    fn apply(self, world: &mut World) {
+        let gate = world.spawn((
+            Sprite::from_color(GATE_COLOR, self.size),
+            Transform::from_translation(self.position.extend(0.0)),
+            Gate {
+                size: self.size,
+                open: self.open,
+            },
+            LevelObstacle,
+            OnGameScreen,
+        ));
+        update_gate(gate);
+    }
+}
+
+pub struct SpawnLock {
+    pub position: Vec2,
+}
+
+impl Command for SpawnLock {
+    type Out = ();
+
+    fn apply(self, world: &mut World) {
+        world.spawn((
+            Sprite::from_color(LOCK_COLOR, STONE_SIZE),
+            Transform::from_translation(self.position.extend(0.0)),
+            Collider {
+                size: Some(STONE_SIZE),
+                obstacle: Obstacle::Wall,
+            },
+            Lock,
+            LevelObstacle,
+            OnGameScreen,
+        ));
+    }
+}
+
+// An open gate has no collider, so that the ball passes through it
+fn update_gate(mut gate: EntityWorldMut) {
+    let Some(&Gate { size, open }) = gate.get::<Gate>() else {
+        return;
+    };
+
+    if open {
+        gate.remove::<Collider>();
+    } else {
+        gate.insert(Collider {
+            size: Some(size),
+            obstacle: Obstacle::Wall,
+        });
+    }
+
+    if let Some(mut sprite) = gate.get_mut::<Sprite>() {
+        sprite
+            .color
+            .set_alpha(if open { OPEN_GATE_ALPHA } else { 1.0 });
+    }
+}
+
+// Any number of hits in the same tick toggles the gates only once
+fn toggle_gates(
+    mut commands: Commands,
+    mut collision_messages: MessageReader<CollisionMessage>,
+    mut gates: Query<(Entity, &mut Gate)>,
+) {
+    let switched = collision_messages
+        .read()
+        .filter(|message| matches!(message.obstacle, Obstacle::Switch))
+        .count()
+        > 0;
+    if !switched {
+        return;
+    }
+
+    for (entity, mut gate) in &mut gates {
+        gate.open = !gate.open;
+        commands.entity(entity).queue(update_gate);
+    }
+}
+
+// Hidden stones appear with the first lost hit point, whether the ball, an explosion or the
+// laser took it
+pub fn reveal_hidden_stones(
+    mut commands: Commands,
+    hidden_stones: Query<(Entity, &Stone), With<Hidden>>,
+) {
+    for (entity, stone) in &hidden_stones {
+        if stone.hit_points < StoneKind::Hidden.hit_points() {
+            commands
+                .entity(entity)
+                .remove::<Hidden>()
+                .insert(Visibility::Inherited);
+        }
+    }
+}
+
+fn unlock_locks(
+    destroyed: On<StoneDestroyed>,
+    mut commands: Commands,
+    keys: Query<&Stone, With<Key>>,
+    locks: Query<Entity, With<Lock>>,
+) {
+    if !keys.contains(destroyed.entity) || keys.iter().any(|stone| stone.hit_points > 0) {
+        return;
+    }
+
+    for lock in &locks {
+        commands.entity(lock).despawn();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        destroy_stone,
+        testing::{spawn_ball_below, test_app},
+        Ball, Destroyed, SpawnStone,
+    };
+
+    #[test]
+    fn test_switch_toggles_gates() {
+        let mut app = test_app();
+
+        app.add_observer(destroy_stone)
+            .add_observer(unlock_locks)
+            .add_systems(
+                Update,
+                (check_for_collisions, (toggle_gates, reveal_hidden_stones)).chain(),
+            );
+
+        let switch = Vec2::new(0.0, 0.0);
+        app.world_mut()
+            .commands()
+            .queue(SpawnSwitch { position: switch });
+        for (x, open) in [(-300.0, false), (300.0, true)] {
+            app.world_mut().commands().queue(SpawnGate {
+                position: Vec2::new(x, 0.0),
+                size: Vec2::new(200.0, 20.0),
+                open,
+            });
+        }
+        app.world_mut().flush();
+        let ball = spawn_ball_below(app.world_mut(), switch);
+
+        app.update();
+
+        let world = app.world_mut();
+        let mut gates: Vec<_> = world
+            .query::<(&Transform, &Gate, Has<Collider>)>()
+            .iter(world)
+            .map(|(transform, gate, collider)| (transform.translation.x, gate.open, collider))
+            .collect();
+        gates.sort_by(|a, b| a.0.total_cmp(&b.0));
+        assert_eq!(gates, [(-300.0, true, false), (300.0, false, true)]);
+
+        // The switch is indestructible and bounces the ball like a wall
+        assert!(world.get::<Ball>(ball).unwrap().velocity.y < 0.0);
+        assert_eq!(world.query::<&Switch>().iter(world).count(), 1);
+    }
+
+    #[test]
+    fn test_keys_unlock_locks_and_hidden_stones_appear() {
+        let mut app = test_app();
+
+        app.add_observer(destroy_stone)
+            .add_observer(unlock_locks)
+            .add_systems(
+                Update,
+                (check_for_collisions, (toggle_gates, reveal_hidden_stones)).chain(),
+            );
+
+        let stones = [
+            (Vec2::new(-200.0, 0.0), StoneKind::Key),
+            (Vec2::new(200.0, 0.0), StoneKind::Key),
+            (Vec2::new(0.0, 200.0), StoneKind::Hidden),
+        ];
+        for (position, kind) in stones {
+            app.world_mut().commands().queue(SpawnStone {
+                x: position.x,
+                y: position.y,
+                hit_points: kind.hit_points(),
+                kind,
+                moving: None,
+            });
+        }
+        app.world_mut().commands().queue(SpawnLock {
+            position: Vec2::new(0.0, 400.0),
+        });
+        app.world_mut().flush();
+
+        // Touching the hidden stone only reveals it
+        let hidden = spawn_ball_below(app.world_mut(), stones[2].0);
+        app.update();
+        let world = app.world_mut();
+        let (stone, visibility) = world
+            .query_filtered::<(&Stone, &Visibility), Without<Key>>()
+            .single(world)
+            .unwrap();
+        assert_eq!(stone.hit_points, 1);
+        assert_eq!(*visibility, Visibility::Inherited);
+        assert_eq!(world.query::<&Hidden>().iter(world).count(), 0);
+        world.despawn(hidden);
+
+        // The lock disappears only with the last key
+        for (position, _) in &stones[..2] {
+            let world = app.world_mut();
+            assert_eq!(world.query::<&Lock>().iter(world).count(), 1);
+            let ball = spawn_ball_below(app.world_mut(), *position);
+            app.update();
+            app.world_mut().despawn(ball);
+        }
+        let world = app.world_mut();
+        assert_eq!(
+            world
+                .query_filtered::<(), (With<Key>, With<Destroyed>)>()
+                .iter(world)
+                .count(),
+            2
+        );
+        assert_eq!(world.query::<&Lock>().iter(world).count(), 0);
+    }
+}
@@ -1,4 +1,5 @@
 use bevy::{
+    audio::Volume,
     camera::ScalingMode,
     input::{common_conditions::input_just_pressed, mouse::MouseMotion},
     math::bounding::{Aabb2d, BoundingCircle, BoundingVolume, IntersectsVolume},
@@ -6,30 +7,132 @@ use bevy::{
     window::{CursorOptions, PrimaryWindow},
 };
 
+use animation::{OnFinish, SpriteAnimation};
+use attract::TitleMode;
+use autopilot::Autopilot;
+use explosion::Explosive;
+use level::{level_layout, LevelObstacle, StoneKind};
+use loading::{available_image, GameAssets};
+use logic::{Hidden, Key, SpawnGate, SpawnLock, SpawnSwitch};
+use moving::MovingObstacle;
+use rng::GameRng;
+use save::{SaveFile, SavedGame};
+use stall::Stall;
+
+mod animation;
+mod attract;
+mod autopilot;
+mod explosion;
+mod headless;
+mod laser;
+mod level;
+mod loading;
+mod logic;
+mod moving;
+mod particles;
+mod power_ups;
+mod replay;
+mod rng;
+mod safety_net;
+mod save;
+mod screen_effects;
+mod stall;
+mod sticky;
+#[cfg(test)]
+mod testing;
+mod trail;
+
 const MAX_X: f32 = 1920.0;
 const MAX_Y: f32 = 1200.0;
 const WALL_THICKNESS: f32 = 20.0;
 const BALL_RADIUS: f32 = 12.0;
 const BALL_SPEED: f32 = 600.0;
+const BALL_START: Vec2 = Vec2::new(
+    0.0,
+    -MAX_Y / 2.0 + WALL_THICKNESS + MARGIN + BALL_RADIUS * 2.0,
+);
+// The range of angles to the horizontal at which the ball is launched
+const BALL_LAUNCH_ANGLES: std::ops::Range<f32> = 30.0..60.0;
 const MARGIN: f32 = 12.0;
+// Near the corners of an obstacle, the ball is reflected on both axes
+const CORNER_NORMAL: f32 = 0.4;
 const STONE_SIZE: Vec2 = Vec2::new(82.0, 28.0);
 const BAT_SIZE: Vec2 = Vec2::new(124.0, 28.0);
 const BAT_LEFT_BORDER: f32 = -(MAX_X / 2.0) + WALL_THICKNESS + BAT_SIZE.x / 2.0;
 const BAT_RIGHT_BORDER: f32 = -BAT_LEFT_BORDER;
+// The gameplay runs with a fixed timestep, so that it is deterministic and can be replayed
+const TICKS_PER_SECOND: f64 = 60.0;
+const STONE_ANIMATION_FRAMES: usize = 10;
+const STONE_ANIMATION_FPS: f32 = 60.0;
+// Used if the sprites are not available, e.g. in tests without an `AssetServer`
+const BALL_PLACEHOLDER_COLOR: Color = Color::srgb(0.9, 0.9, 0.9);
+const BAT_PLACEHOLDER_COLOR: Color = Color::srgb(0.4, 0.6, 0.9);
+const STONE_PLACEHOLDER_COLOR: Color = Color::srgb(0.7, 0.7, 0.65);
+// Stones that need more than one hit are tinted until only one hit is left
+const TOUGH_STONE_COLOR: Color = Color::srgb(0.55, 0.65, 1.0);
+const EXPLOSIVE_STONE_COLOR: Color = Color::srgb(1.0, 0.45, 0.35);
+const KEY_STONE_COLOR: Color = Color::srgb(1.0, 0.85, 0.3);
+const BARRIER_COLOR: Color = Color::srgb(0.8, 0.5, 0.3);
 
 #[derive(Clone, Copy, Default, Eq, PartialEq, Debug, Hash, States)]
 pub enum GameState {
     #[default]
+    Loading,
     Title,
     Game,
 }
 
-#[derive(Component)]
+// The phases of every tick in `FixedUpdate`, and of every frame in `Update`, in the order in
+// which they run. Plugins add their systems to a phase, or before or after one of them, so that
+// messages written in `FixedUpdate` are handled in the same tick. Systems in `Update`, like the
+// sounds and effects, read the messages of all ticks that ran since the last frame instead.
+#[derive(SystemSet, Clone, Copy, Eq, PartialEq, Debug, Hash)]
+enum GameSet {
+    // Reading, replaying or recording the input, and moving the bat
+    Input,
+    // Moving the balls and the obstacles
+    Physics,
+    // Detecting collisions and changing the balls and stones accordingly
+    Resolve,
+    // Score, combo, sounds and effects, which react to the latest collisions
+    Feedback,
+    // Despawning entities and ending the game
+    Cleanup,
+}
+
+impl GameSet {
+    const ORDER: (Self, Self, Self, Self, Self) = (
+        GameSet::Input,
+        GameSet::Physics,
+        GameSet::Resolve,
+        GameSet::Feedback,
+        GameSet::Cleanup,
+    );
+}
+
+// Whether the balls are moving, either in a game or in the demo on the title screen
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Hash)]
+struct Playing;
+
+impl ComputedStates for Playing {
+    type SourceStates = (GameState, Option<TitleMode>);
+
+    fn compute((game_state, title_mode): (GameState, Option<TitleMode>)) -> Option<Self> {
+        match (game_state, title_mode) {
+            (GameState::Game, _) | (_, Some(TitleMode::Demo)) => Some(Playing),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
 struct Ball {
     velocity: Vec2,
 }
 
-#[derive(Component)]
+#[derive(Component, Reflect)]
+#[reflect(Component)]
 struct Bat;
 
 #[derive(Clone, Copy)]
@@ -37,6 +140,8 @@ enum Obstacle {
     Bat,
     Stone,
     Wall,
+    SafetyNet,
+    Switch,
 }
 
 #[derive(Component)]
@@ -45,12 +150,53 @@ struct Collider {
     obstacle: Obstacle,
 }
 
+impl Collider {
+    // Walls have no size of their own and are scaled instead
+    fn bounds(&self, transform: &Transform) -> Aabb2d {
+        Aabb2d::new(
+            transform.translation.truncate(),
+            self.size.unwrap_or(transform.scale.truncate()) / 2.,
+        )
+    }
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct Stone {
+    // The number of hits until the stone is destroyed
+    hit_points: u32,
+}
+
+// A stone that was hit for the last time. It is no obstacle anymore and is only animated
+// until it despawns.
 #[derive(Component)]
-struct Stone;
+struct Destroyed;
+
+// Triggered once for every stone that loses its last hit point. The collision check only
+// triggers it, everything that happens to a destroyed stone is done by observers.
+#[derive(EntityEvent)]
+struct StoneDestroyed {
+    entity: Entity,
+}
+
+// An indestructible obstacle that moves through the playfield
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct Barrier {
+    size: Vec2,
+}
 
 #[derive(Message)]
 struct CollisionMessage {
+    // The ball, or a projectile fired by the bat
+    ball: Entity,
+    obstacle_entity: Entity,
     obstacle: Obstacle,
+    // The point on the obstacle's bounding box that is closest to the ball
+    point: Vec2,
+    side: Collision,
+    // The speed of the ball towards the obstacle before it was reflected
+    impact_speed: f32,
 }
 
 #[derive(Component)]
@@ -59,16 +205,40 @@ struct OnTitleScreen;
 #[derive(Component)]
 struct OnGameScreen;
 
-#[derive(Default, Resource)]
+#[derive(Default, Reflect, Resource)]
+#[reflect(Resource)]
 struct Score(u64);
 
-#[derive(Component)]
-struct Despawning(Timer);
+// Everything besides the input of the player that determines the course of a game
+#[derive(Clone, Copy, Default, Eq, PartialEq, Debug, Reflect, Resource)]
+#[reflect(Resource)]
+struct GameSetup {
+    seed: u64,
+    level: u32,
+}
+
+// The seed of the last game that was played, which allows to play the same game again with `--seed`
+#[derive(Resource)]
+struct LastSeed(u64);
+
+// The input of the player for the next tick, collected from every frame since the last tick
+#[derive(Clone, Copy, Default, PartialEq, Debug, Resource)]
+struct BatInput {
+    movement: f32,
+    // Whether the left mouse button was pressed
+    click: bool,
+}
+
+// The number of stones destroyed since the ball last touched the bat
+#[derive(Default, Resource)]
+struct Combo(u32);
 
 enum WallLocation {
     Top,
     Left,
     Right,
+    // Only used for the safety net in assist mode, the ball is lost at the bottom otherwise
+    Bottom,
 }
 
 impl WallLocation {
@@ -77,12 +247,13 @@ impl WallLocation {
             WallLocation::Top => Vec3::new(0.0, MAX_Y / 2.0, 0.0),
             WallLocation::Left => Vec3::new(-MAX_X / 2.0, 0.0, 0.0),
             WallLocation::Right => Vec3::new(MAX_X / 2.0, 0.0, 0.0),
+            WallLocation::Bottom => Vec3::new(0.0, -MAX_Y / 2.0, 0.0),
         }
     }
 
     fn size(&self) -> Vec3 {
         match self {
-            WallLocation::Top => Vec3::new(MAX_X, WALL_THICKNESS, 0.0),
+            WallLocation::Top | WallLocation::Bottom => Vec3::new(MAX_X, WALL_THICKNESS, 0.0),
             WallLocation::Left | WallLocation::Right => Vec3::new(WALL_THICKNESS, MAX_Y, 0.0),
         }
     }
@@ -111,40 +282,154 @@ impl Command for SpawnWall {
 struct SpawnStone {
     x: f32,
     y: f32,
+    hit_points: u32,
+    kind: StoneKind,
+    moving: Option<MovingObstacle>,
 }
 
 impl Command for SpawnStone {
     type Out = ();
 
     fn apply(self, world: &mut World) {
-        let layout = TextureAtlasLayout::from_grid(
-            UVec2::new(STONE_SIZE.x as u32, STONE_SIZE.y as u32), /*tile_size*/
-            10,                                                   /*columns*/
-            1,                                                    /*rows*/
-            None,                                                 /*padding*/
-            None,                                                 /*offset*/
-        );
-        let texture_atlas_layouts = world.get_resource_mut::<Assets<TextureAtlasLayout>>();
-        let texture_atlas_layout = texture_atlas_layouts.unwrap().add(layout);
-
-        if let Some(asset_server) = world.get_resource::<AssetServer>() {
-            world.spawn((
-                Sprite::from_atlas_image(
-                    asset_server.load("sprites/stone-animated.png"),
-                    TextureAtlas {
-                        layout: texture_atlas_layout,
-                        index: 0,
-                    },
-                ),
-                Transform::from_xyz(self.x, self.y, 0.0),
-                Collider {
-                    size: Some(STONE_SIZE),
-                    obstacle: Obstacle::Stone,
+        let sprite = match available_image(world, |game_assets| &game_assets.stone) {
+            Some(image) => Sprite::from_atlas_image(
+                image,
+                TextureAtlas {
+                    layout: world.resource::<GameAssets>().stone_layout.clone(),
+                    index: 0,
                 },
-                Stone,
+            ),
+            None => Sprite::from_color(STONE_PLACEHOLDER_COLOR, STONE_SIZE),
+        };
+
+        let mut stone = world.spawn((
+            sprite,
+            Transform::from_xyz(self.x, self.y, 0.0),
+            Collider {
+                size: Some(STONE_SIZE),
+                obstacle: Obstacle::Stone,
+            },
+            Stone {
+                hit_points: self.hit_points,
+            },
+            OnGameScreen,
+        ));
+
+        match self.kind {
+            StoneKind::Normal | StoneKind::Tough => {}
+            StoneKind::Explosive => {
+                stone.insert(Explosive);
+            }
+            StoneKind::Key => {
+                stone.insert(Key);
+            }
+            StoneKind::Hidden => {
+                stone.insert((Hidden, Visibility::Hidden));
+            }
+        }
+        if let Some(moving) = self.moving {
+            stone.insert(moving);
+        }
+    }
+}
+
+struct SpawnBarrier {
+    size: Vec2,
+    moving: MovingObstacle,
+}
+
+impl Command for SpawnBarrier {
+    type Out = ();
+
+    fn apply(self, world: &mut World) {
+        // Barriers bounce the ball like walls
+        world.spawn((
+            Sprite::from_color(BARRIER_COLOR, self.size),
+            Transform::from_translation(self.moving.position().extend(0.0)),
+            Collider {
+                size: Some(self.size),
+                obstacle: Obstacle::Wall,
+            },
+            Barrier { size: self.size },
+            self.moving,
+            LevelObstacle,
+            OnGameScreen,
+        ));
+    }
+}
+
+struct SpawnBall {
+    position: Vec2,
+    velocity: Vec2,
+}
+
+impl SpawnBall {
+    // Like the command, but returns the ball, so that a saved game can link it to its bat
+    fn spawn(self, world: &mut World) -> Entity {
+        let sprite = match available_image(world, |game_assets| &game_assets.ball) {
+            Some(image) => Sprite::from_image(image),
+            None => Sprite::from_color(BALL_PLACEHOLDER_COLOR, Vec2::splat(BALL_RADIUS * 2.0)),
+        };
+
+        world
+            .spawn((
+                sprite,
+                Transform::from_translation(self.position.extend(0.0)),
+                Ball {
+                    velocity: self.velocity,
+                },
+                Stall::default(),
                 OnGameScreen,
-            ));
+            ))
+            .id()
+    }
+}
+
+impl Command for SpawnBall {
+    type Out = ();
+
+    fn apply(self, world: &mut World) {
+        self.spawn(world);
+    }
+}
+
+struct SpawnBat {
+    x: f32,
+    // Lets the AI move the bat instead of the player
+    autopilot: Option<Autopilot>,
+}
+
+impl SpawnBat {
+    // Like the command, but returns the bat, so that a saved game can give it its power-ups
+    fn spawn(self, world: &mut World) -> Entity {
+        let sprite = match available_image(world, |game_assets| &game_assets.bat) {
+            Some(image) => Sprite::from_image(image),
+            None => Sprite::from_color(BAT_PLACEHOLDER_COLOR, BAT_SIZE),
+        };
+
+        let mut bat = world.spawn((
+            sprite,
+            Transform::from_xyz(self.x, -MAX_Y / 2.0 + WALL_THICKNESS + MARGIN, 0.0),
+            Collider {
+                size: Some(BAT_SIZE),
+                obstacle: Obstacle::Bat,
+            },
+            Bat,
+            OnGameScreen,
+        ));
+
+        if let Some(autopilot) = self.autopilot {
+            bat.insert(autopilot);
         }
+        bat.id()
+    }
+}
+
+impl Command for SpawnBat {
+    type Out = ();
+
+    fn apply(self, world: &mut World) {
+        self.spawn(world);
     }
 }
 
@@ -156,6 +441,7 @@ fn setup(
 
     commands.spawn((
         Camera2d,
+        screen_effects::ScreenShake::default(),
         Projection::Orthographic(OrthographicProjection {
             scaling_mode: ScalingMode::AutoMin {
                 min_width: MAX_X,
@@ -166,9 +452,84 @@ fn setup(
     ));
 }
 
-fn setup_game(mut commands: Commands, asset_server: Res<AssetServer>, mut score: ResMut<Score>) {
+fn setup_game(
+    mut commands: Commands,
+    mut score: ResMut<Score>,
+    mut combo: ResMut<Combo>,
+    mut bat_input: ResMut<BatInput>,
+    mut rng: ResMut<GameRng>,
+    game_setup: Res<GameSetup>,
+) {
     score.0 = 0;
+    combo.0 = 0;
+    *bat_input = BatInput::default();
 
+    spawn_playfield(&mut commands, &mut rng, game_setup.level, None);
+}
+
+// Spawns walls, ball, bat and stones, used by the game and by the demo on the title screen
+fn spawn_playfield(
+    commands: &mut Commands,
+    rng: &mut GameRng,
+    level: u32,
+    autopilot: Option<Autopilot>,
+) {
+    spawn_walls(commands);
+    launch_ball(commands, rng);
+    commands.queue(SpawnBat { x: 0.0, autopilot });
+    spawn_level(commands, rng, level);
+}
+
+// The ball is launched upwards from the bat, either to the left or to the right
+fn launch_ball(commands: &mut Commands, rng: &mut GameRng) {
+    let angle = rng.range(BALL_LAUNCH_ANGLES).to_radians();
+    let direction = Vec2::from_angle(angle);
+    let direction = if rng.chance(0.5) {
+        direction
+    } else {
+        Vec2::new(-direction.x, direction.y)
+    };
+
+    commands.queue(SpawnBall {
+        position: BALL_START,
+        velocity: direction * BALL_SPEED,
+    });
+}
+
+// Spawns the stones and barriers of `level`
+fn spawn_level(commands: &mut Commands, rng: &mut GameRng, level: u32) {
+    let layout = level_layout(level, rng);
+    for stone in layout.stones {
+        commands.queue(SpawnStone {
+            x: stone.position.x,
+            y: stone.position.y,
+            hit_points: stone.kind.hit_points(),
+            kind: stone.kind,
+            moving: stone.path.map(MovingObstacle::new),
+        });
+    }
+    for barrier in layout.barriers {
+        commands.queue(SpawnBarrier {
+            size: barrier.size,
+            moving: MovingObstacle::new(barrier.path),
+        });
+    }
+    for position in layout.switches {
+        commands.queue(SpawnSwitch { position });
+    }
+    for gate in layout.gates {
+        commands.queue(SpawnGate {
+            position: gate.position,
+            size: gate.size,
+            open: gate.open,
+        });
+    }
+    for position in layout.locks {
+        commands.queue(SpawnLock { position });
+    }
+}
+
+fn spawn_walls(commands: &mut Commands) {
     commands.queue(SpawnWall {
         location: WallLocation::Top,
     });
@@ -178,44 +539,6 @@ fn setup_game(mut commands: Commands, asset_server: Res<AssetServer>, mut score:
     commands.queue(SpawnWall {
         location: WallLocation::Right,
     });
-
-    commands.spawn((
-        Sprite::from_image(asset_server.load("sprites/ball.png")),
-        Transform::from_xyz(
-            0.0,
-            -MAX_Y / 2.0 + WALL_THICKNESS + MARGIN + BALL_RADIUS * 2.0,
-            0.0,
-        ),
-        Ball {
-            velocity: Vec2::new(0.5, 0.5).normalize() * BALL_SPEED,
-        },
-        OnGameScreen,
-    ));
-
-    commands.spawn((
-        Sprite::from_image(asset_server.load("sprites/bat.png")),
-        Transform::from_xyz(0.0, -MAX_Y / 2.0 + WALL_THICKNESS + MARGIN, 0.0),
-        Collider {
-            size: Some(BAT_SIZE),
-            obstacle: Obstacle::Bat,
-        },
-        Bat,
-        OnGameScreen,
-    ));
-
-    for x in (((-MAX_X / 2.0 + WALL_THICKNESS / 2.0 + MARGIN + STONE_SIZE.x / 2.0 + 3.0) as i32)
-        ..(MAX_X / 2.0) as i32)
-        .step_by((STONE_SIZE.x + MARGIN) as usize)
-    {
-        for y in (0..((MAX_Y / 2.0 - WALL_THICKNESS / 2.0 - MARGIN - STONE_SIZE.y / 2.0) as i32))
-            .step_by((STONE_SIZE.y + MARGIN) as usize)
-        {
-            commands.queue(SpawnStone {
-                x: x as f32,
-                y: y as f32,
-            });
-        }
-    }
 }
 
 fn apply_velocity(mut balls: Query<(&Ball, &mut Transform)>, time: Res<Time>) {
@@ -225,72 +548,168 @@ fn apply_velocity(mut balls: Query<(&Ball, &mut Transform)>, time: Res<Time>) {
     }
 }
 
+// A single obstacle that the ball touches in this tick
+struct Contact {
+    bounds: Aabb2d,
+    // Points away from the obstacle, diagonally at its corners
+    normal: Vec2,
+    depth: f32,
+    velocity: Vec2,
+}
+
 fn check_for_collisions(
     mut commands: Commands,
-    mut balls: Query<(&mut Ball, &Transform)>,
-    obstacles: Query<(Entity, &Transform, &Collider, Option<&Stone>)>,
+    mut balls: Query<(Entity, &mut Ball, &mut Transform)>,
+    mut obstacles: Query<(Entity, &Transform, &Collider, Option<&mut Stone>), Without<Ball>>,
+    moving_obstacles: Query<&MovingObstacle>,
     mut collision_messages: MessageWriter<CollisionMessage>,
 ) {
-    for (mut ball, ball_transform) in &mut balls {
-        for (entity, obstacle, collider, maybe_stone) in &obstacles {
-            let collision = ball_collision(
-                BoundingCircle::new(ball_transform.translation.truncate(), BALL_RADIUS),
-                Aabb2d::new(
-                    obstacle.translation.truncate(),
-                    collider.size.unwrap_or(obstacle.scale.truncate()) / 2.,
-                ),
-            );
+    for (ball_entity, mut ball, mut ball_transform) in &mut balls {
+        let ball_bounds = BoundingCircle::new(ball_transform.translation.truncate(), BALL_RADIUS);
+
+        // All contacts are collected first, so that the ball is reflected only once,
+        // even if it touches two stones next to each other or a stone and a wall
+        let mut contacts = Vec::new();
+        for (entity, obstacle, collider, maybe_stone) in &mut obstacles {
+            // The collider of a stone destroyed by another ball in this tick is not removed yet
+            if maybe_stone
+                .as_ref()
+                .is_some_and(|stone| stone.hit_points == 0)
+            {
+                continue;
+            }
+
+            let obstacle_bounds = collider.bounds(obstacle);
+            let Some(collision) = ball_collision(ball_bounds, obstacle_bounds) else {
+                continue;
+            };
+
+            // The ball bounces off a moving obstacle as seen from the obstacle
+            let obstacle_velocity = moving_obstacles
+                .get(entity)
+                .map_or(Vec2::ZERO, MovingObstacle::velocity);
+            let push = push_out(ball_bounds, obstacle_bounds);
 
-            if let Some(collision) = collision {
-                collision_messages.write(CollisionMessage {
-                    obstacle: collider.obstacle,
-                });
-
-                if maybe_stone.is_some() {
-                    commands
-                        .entity(entity)
-                        .insert(Despawning(Timer::from_seconds(0.01, TimerMode::Repeating)));
-                }
-
-                // Reflect the ball's velocity when it collides
-                let mut reflect_x = false;
-                let mut reflect_y = false;
-
-                // Reflect only if the velocity is in the opposite direction of the collision
-                // This prevents the ball from getting stuck inside the bar
-                match collision {
-                    Collision::Left => reflect_x = ball.velocity.x > 0.0,
-                    Collision::Right => reflect_x = ball.velocity.x < 0.0,
-                    Collision::Top => reflect_y = ball.velocity.y < 0.0,
-                    Collision::Bottom => reflect_y = ball.velocity.y > 0.0,
-                }
-
-                // Reflect velocity on the x-axis if we hit something on the x-axis
-                if reflect_x {
-                    ball.velocity.x = -ball.velocity.x;
-                }
-
-                // Reflect velocity on the y-axis if we hit something on the y-axis
-                if reflect_y {
-                    ball.velocity.y = -ball.velocity.y;
-                }
+            collision_messages.write(CollisionMessage {
+                ball: ball_entity,
+                obstacle_entity: entity,
+                obstacle: collider.obstacle,
+                point: obstacle_bounds.closest_point(ball_bounds.center()),
+                side: collision,
+                impact_speed: (ball.velocity - obstacle_velocity)
+                    .dot(-collision.normal())
+                    .max(0.0),
+            });
+
+            if let Some(mut stone) = maybe_stone {
+                hit_stone(&mut commands, entity, &mut stone);
             }
+
+            contacts.push(Contact {
+                bounds: obstacle_bounds,
+                normal: push.normalize_or(collision.normal()),
+                depth: push.length(),
+                velocity: obstacle_velocity,
+            });
+        }
+
+        let Some(deepest) = contacts.iter().max_by(|a, b| a.depth.total_cmp(&b.depth)) else {
+            continue;
+        };
+
+        // The corner of a stone next to another one is ignored, so that both act like a single
+        // flat side. A stone and a wall, or the corner of a single stone, give a diagonal normal.
+        let sides = contacts
+            .iter()
+            .map(|contact| contact.normal)
+            .filter(|normal| normal.x == 0.0 || normal.y == 0.0)
+            .sum::<Vec2>();
+        let normal = if sides == Vec2::ZERO {
+            contacts.iter().map(|contact| contact.normal).sum()
+        } else {
+            sides
+        }
+        .normalize_or(deepest.normal);
+        let obstacle_velocity = deepest.velocity;
+        let mut velocity = ball.velocity - obstacle_velocity;
+
+        // Reflect only if the velocity is in the opposite direction of the collision
+        // This prevents the ball from getting stuck inside the bar
+        if normal.x.abs() > CORNER_NORMAL && velocity.x * normal.x < 0.0 {
+            velocity.x = -velocity.x;
+        }
+        if normal.y.abs() > CORNER_NORMAL && velocity.y * normal.y < 0.0 {
+            velocity.y = -velocity.y;
+        }
+
+        // The direction changes with the movement of the obstacle, but not the speed.
+        // It never becomes so flat that the ball would take forever to come back.
+        let speed = ball.velocity.length();
+        ball.velocity = (velocity + obstacle_velocity)
+            .try_normalize()
+            .map_or(ball.velocity, |direction| {
+                stall::with_min_vertical_angle(direction * speed)
+            });
+
+        // The ball never stays inside an obstacle, which would reflect it again in the next tick
+        let mut center = ball_bounds.center();
+        for contact in &contacts {
+            center += push_out(BoundingCircle::new(center, BALL_RADIUS), contact.bounds);
         }
+        ball_transform.translation.x = center.x;
+        ball_transform.translation.y = center.y;
+    }
+}
+
+// Takes a hit point from the stone, which is destroyed with the last one
+fn hit_stone(commands: &mut Commands, entity: Entity, stone: &mut Stone) {
+    stone.hit_points = stone.hit_points.saturating_sub(1);
+    if stone.hit_points == 0 {
+        commands.trigger(StoneDestroyed { entity });
     }
 }
 
-fn move_bat(
+// The stone is no obstacle anymore and despawns at the end of its animation
+fn destroy_stone(destroyed: On<StoneDestroyed>, mut commands: Commands) {
+    commands
+        .entity(destroyed.entity)
+        .remove::<Collider>()
+        .insert((
+            Destroyed,
+            SpriteAnimation::once(
+                0,
+                STONE_ANIMATION_FRAMES - 1,
+                STONE_ANIMATION_FPS,
+                OnFinish::Despawn,
+            ),
+        ));
+}
+
+// Collects the mouse movement and clicks of every frame until the next tick
+fn read_bat_input(
     mut motion: MessageReader<MouseMotion>,
-    mut bat_query: Query<&mut Transform, With<Bat>>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    mut bat_input: ResMut<BatInput>,
 ) {
     for message in motion.read() {
-        for mut bat in &mut bat_query {
-            bat.translation.x += message.delta.x * 2.0;
-            bat.translation.x = bat.translation.x.clamp(BAT_LEFT_BORDER, BAT_RIGHT_BORDER);
-        }
+        bat_input.movement += message.delta.x * 2.0;
+    }
+    bat_input.click |= mouse_buttons.just_pressed(MouseButton::Left);
+}
+
+fn move_bat(bat_input: Res<BatInput>, mut bat_query: Query<&mut Transform, With<Bat>>) {
+    for mut bat in &mut bat_query {
+        bat.translation.x += bat_input.movement;
+        bat.translation.x = bat.translation.x.clamp(BAT_LEFT_BORDER, BAT_RIGHT_BORDER);
     }
 }
 
+// The input is used by every system of the tick, and collected anew for the next one
+fn clear_bat_input(mut bat_input: ResMut<BatInput>) {
+    *bat_input = BatInput::default();
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
 enum Collision {
     Left,
     Right,
@@ -298,6 +717,18 @@ enum Collision {
     Bottom,
 }
 
+impl Collision {
+    // The outward facing normal of the side that was hit
+    fn normal(&self) -> Vec2 {
+        match self {
+            Collision::Left => Vec2::NEG_X,
+            Collision::Right => Vec2::X,
+            Collision::Top => Vec2::Y,
+            Collision::Bottom => Vec2::NEG_Y,
+        }
+    }
+}
+
 // Returns `Some` if `ball` collides with `bounding_box`.
 // The returned `Collision` is the side of `bounding_box` that `ball` hit.
 fn ball_collision(ball: BoundingCircle, bounding_box: Aabb2d) -> Option<Collision> {
@@ -322,52 +753,140 @@ fn ball_collision(ball: BoundingCircle, bounding_box: Aabb2d) -> Option<Collisio
     Some(side)
 }
 
-fn despawn_stones(
-    mut commands: Commands,
-    time: Res<Time>,
-    mut stones: Query<(Entity, &mut Sprite, &mut Despawning)>,
-) {
-    for (entity, mut sprite, mut despawning) in &mut stones {
-        despawning.0.tick(time.delta());
-        if despawning.0.just_finished() {
-            if let Some(atlas) = &mut sprite.texture_atlas {
-                if atlas.index < 9 {
-                    atlas.index += 1;
-                } else {
-                    commands.entity(entity).despawn();
-                }
-            }
-        }
+// The shortest movement that takes `ball` out of `bounding_box`, or zero if they do not overlap
+fn push_out(ball: BoundingCircle, bounding_box: Aabb2d) -> Vec2 {
+    let offset = ball.center() - bounding_box.closest_point(ball.center());
+    let distance = offset.length();
+    if distance >= ball.radius() {
+        return Vec2::ZERO;
     }
+    if distance > 0.0 {
+        return offset / distance * (ball.radius() - distance);
+    }
+
+    // The center of the ball is inside the box, so it leaves through the closest side
+    let center = ball.center();
+    [
+        (Vec2::NEG_X, center.x - bounding_box.min.x),
+        (Vec2::X, bounding_box.max.x - center.x),
+        (Vec2::NEG_Y, center.y - bounding_box.min.y),
+        (Vec2::Y, bounding_box.max.y - center.y),
+    ]
+    .into_iter()
+    .min_by(|a, b| a.1.total_cmp(&b.1))
+    .map_or(Vec2::ZERO, |(normal, distance)| {
+        normal * (distance + ball.radius())
+    })
 }
 
 fn play_sounds(
     mut commands: Commands,
     mut collision_messages: MessageReader<CollisionMessage>,
-    asset_server: Res<AssetServer>,
+    stones: Query<&Stone>,
+    game_assets: Res<GameAssets>,
 ) {
     for message in collision_messages.read() {
-        match message.obstacle {
-            Obstacle::Bat => commands.spawn((
-                AudioPlayer::new(asset_server.load("sounds/bat.ogg")),
-                PlaybackSettings::DESPAWN,
-            )),
-            Obstacle::Stone => commands.spawn((
-                AudioPlayer::new(asset_server.load("sounds/stone.ogg")),
-                PlaybackSettings::DESPAWN,
-            )),
-            Obstacle::Wall => commands.spawn((
-                AudioPlayer::new(asset_server.load("sounds/wall.ogg")),
-                PlaybackSettings::DESPAWN,
-            )),
+        trace!(
+            "Ball {} hit the {:?} side of {} at {}",
+            message.ball,
+            message.side,
+            message.obstacle_entity,
+            message.point
+        );
+
+        let sound = match message.obstacle {
+            Obstacle::Bat => &game_assets.bat_sound,
+            // A destroyed stone is heard through `play_destroy_sound`
+            Obstacle::Stone
+                if stones
+                    .get(message.obstacle_entity)
+                    .is_ok_and(|stone| stone.hit_points > 0) =>
+            {
+                &game_assets.stone_sound
+            }
+            Obstacle::Stone => continue,
+            Obstacle::Wall | Obstacle::SafetyNet | Obstacle::Switch => &game_assets.wall_sound,
         };
+
+        // Harder hits are louder, but even a grazing hit stays audible
+        let volume = (message.impact_speed / BALL_SPEED).clamp(0.2, 1.0);
+        play_sound(&mut commands, &game_assets, sound, volume);
+    }
+}
+
+// Also for the stones destroyed by an explosion, which are not hit by the ball
+fn play_destroy_sound(
+    _destroyed: On<StoneDestroyed>,
+    mut commands: Commands,
+    game_state: Res<State<GameState>>,
+    game_assets: Res<GameAssets>,
+) {
+    if *game_state == GameState::Game {
+        play_sound(&mut commands, &game_assets, &game_assets.stone_sound, 1.0);
+    }
+}
+
+// Sounds that failed to load are skipped
+fn play_sound(
+    commands: &mut Commands,
+    game_assets: &GameAssets,
+    sound: &Handle<AudioSource>,
+    volume: f32,
+) {
+    if game_assets.is_available(sound.id()) {
+        commands.spawn((
+            AudioPlayer::new(sound.clone()),
+            PlaybackSettings::DESPAWN.with_volume(Volume::Linear(volume)),
+        ));
     }
 }
 
-fn handle_score(mut collision_messages: MessageReader<CollisionMessage>, mut score: ResMut<Score>) {
+fn tint_stones(
+    mut stones: Query<(Entity, &Stone, &mut Sprite), Changed<Stone>>,
+    explosives: Query<(), With<Explosive>>,
+    keys: Query<(), With<Key>>,
+) {
+    for (entity, stone, mut sprite) in &mut stones {
+        sprite.color = if explosives.contains(entity) {
+            EXPLOSIVE_STONE_COLOR
+        } else if keys.contains(entity) {
+            KEY_STONE_COLOR
+        } else if stone.hit_points > 1 {
+            TOUGH_STONE_COLOR
+        } else if sprite.texture_atlas.is_some() {
+            Color::WHITE
+        } else {
+            STONE_PLACEHOLDER_COLOR
+        };
+    }
+}
+
+// Every stone counts once, when it is destroyed, no matter how many hits it took.
+// The demo on the title screen does not change the score of the last game.
+fn handle_score(
+    _destroyed: On<StoneDestroyed>,
+    game_state: Res<State<GameState>>,
+    mut score: ResMut<Score>,
+) {
+    if *game_state == GameState::Game {
+        score.0 += 100;
+    }
+}
+
+fn extend_combo(
+    _destroyed: On<StoneDestroyed>,
+    game_state: Res<State<GameState>>,
+    mut combo: ResMut<Combo>,
+) {
+    if *game_state == GameState::Game {
+        combo.0 += 1;
+    }
+}
+
+fn reset_combo(mut collision_messages: MessageReader<CollisionMessage>, mut combo: ResMut<Combo>) {
     for message in collision_messages.read() {
-        if let Obstacle::Stone = message.obstacle {
-            score.0 += 100;
+        if let Obstacle::Bat = message.obstacle {
+            combo.0 = 0;
         }
     }
 }
@@ -383,8 +902,14 @@ fn check_for_game_over(
     }
 }
 
-fn setup_title(mut commands: Commands, asset_server: Res<AssetServer>, score: Res<Score>) {
-    let font: FontSource = asset_server.load("fonts/AllertaStencil-Regular.ttf").into();
+fn setup_title(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    score: Res<Score>,
+    last_seed: Option<Res<LastSeed>>,
+    save_file: Res<SaveFile>,
+) {
+    let font = game_assets.font_source();
 
     let title_font = TextFont {
         font: font.clone(),
@@ -392,10 +917,12 @@ fn setup_title(mut commands: Commands, asset_server: Res<AssetServer>, score: Re
         ..default()
     };
 
+    // The texts are placed in front of the demo game
     commands.spawn((
         Text2d::new("Breakout"),
         title_font.clone(),
         TextLayout::justify(Justify::Center),
+        Transform::from_xyz(0.0, 0.0, 2.0),
         OnTitleScreen,
     ));
 
@@ -409,13 +936,38 @@ fn setup_title(mut commands: Commands, asset_server: Res<AssetServer>, score: Re
         Text2d::new(format!("Last score: {}", score.0)),
         score_font.clone(),
         TextLayout::justify(Justify::Center),
-        Transform::from_xyz(0.0, -256.0, 0.0),
+        Transform::from_xyz(0.0, -224.0, 2.0),
         OnTitleScreen,
     ));
 
     if score.0 == 0 {
         score_text.insert(Visibility::Hidden);
     }
+
+    // Also shown for a game without points, which might be worth another try
+    if let Some(last_seed) = last_seed {
+        commands.spawn((
+            Text2d::new(format!("Seed: {}", last_seed.0)),
+            score_font.clone(),
+            TextLayout::justify(Justify::Center),
+            Transform::from_xyz(0.0, -320.0, 2.0),
+            OnTitleScreen,
+        ));
+    }
+
+    if save_file.exists() {
+        commands.spawn((
+            Text2d::new("Press C to continue"),
+            score_font,
+            TextLayout::justify(Justify::Center),
+            Transform::from_xyz(0.0, -416.0, 2.0),
+            OnTitleScreen,
+        ));
+    }
+}
+
+fn remember_seed(mut commands: Commands, game_setup: Res<GameSetup>) {
+    commands.insert_resource(LastSeed(game_setup.seed));
 }
 
 fn start_game(mut game_state: ResMut<NextState<GameState>>) {
@@ -428,37 +980,115 @@ fn despawn_screen<T: Component>(to_despawn: Query<Entity, With<T>>, mut commands
     }
 }
 
+// The gameplay itself, which works without window, audio or renderer
+struct GamePlugin;
+
+impl Plugin for GamePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_state::<GameState>()
+            .add_computed_state::<Playing>()
+            .add_plugins((
+                animation::AnimationPlugin,
+                attract::AttractModePlugin,
+                autopilot::AutopilotPlugin,
+                explosion::ExplosionPlugin,
+                laser::LaserPlugin,
+                moving::MovingObstaclesPlugin,
+                power_ups::PowerUpPlugin,
+                // Before `ReplayPlugin`, which replaces seed and level by the recorded ones
+                rng::RngPlugin,
+                level::LevelPlugin,
+                logic::LevelLogicPlugin,
+                replay::ReplayPlugin,
+                safety_net::SafetyNetPlugin,
+                save::SavePlugin,
+                stall::AntiStallPlugin,
+                sticky::StickyBatPlugin,
+            ))
+            .insert_resource(Time::<Fixed>::from_hz(TICKS_PER_SECOND))
+            .add_systems(
+                OnEnter(GameState::Game),
+                (rng::seed_game, setup_game)
+                    .chain()
+                    .run_if(not(resource_exists::<SavedGame>)),
+            )
+            .add_systems(
+                OnExit(GameState::Game),
+                (despawn_screen::<OnGameScreen>, remember_seed),
+            )
+            .add_systems(
+                Update,
+                (start_game)
+                    .run_if(in_state(GameState::Title))
+                    .run_if(input_just_pressed(KeyCode::Enter)),
+            )
+            .configure_sets(FixedUpdate, GameSet::ORDER.chain())
+            .configure_sets(Update, GameSet::ORDER.chain())
+            .add_systems(
+                Update,
+                read_bat_input
+                    .in_set(GameSet::Input)
+                    .run_if(in_state(GameState::Game)),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    apply_velocity.in_set(GameSet::Physics),
+                    check_for_collisions.in_set(GameSet::Resolve),
+                )
+                    .run_if(in_state(Playing)),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    move_bat.in_set(GameSet::Input),
+                    reset_combo.in_set(GameSet::Feedback),
+                    (check_for_game_over, clear_bat_input).in_set(GameSet::Cleanup),
+                )
+                    .run_if(in_state(GameState::Game)),
+            )
+            .add_message::<CollisionMessage>()
+            .add_observer(destroy_stone)
+            .add_observer(handle_score)
+            .add_observer(extend_combo)
+            .init_resource::<Score>()
+            .init_resource::<Combo>()
+            .init_resource::<GameSetup>()
+            .init_resource::<BatInput>();
+    }
+}
+
+// Returns the value following `name` on the command line
+fn argument_value(name: &str) -> Option<String> {
+    let mut args = std::env::args().skip_while(|arg| arg != name);
+    args.next()?;
+    args.next()
+}
+
 fn main() {
+    if std::env::args().any(|arg| arg == "--headless") {
+        headless::run();
+        return;
+    }
+
     let mut app = App::new();
 
     app.add_plugins(DefaultPlugins)
+        .add_plugins((
+            GamePlugin,
+            loading::LoadingPlugin,
+            particles::ParticlesPlugin,
+            screen_effects::ScreenEffectsPlugin,
+            trail::TrailPlugin,
+        ))
         .add_systems(Startup, setup)
         .add_systems(OnEnter(GameState::Title), setup_title)
         .add_systems(OnExit(GameState::Title), despawn_screen::<OnTitleScreen>)
-        .add_systems(OnEnter(GameState::Game), setup_game)
-        .add_systems(OnExit(GameState::Game), despawn_screen::<OnGameScreen>)
+        .add_observer(play_destroy_sound)
         .add_systems(
             Update,
-            (start_game)
-                .run_if(in_state(GameState::Title))
-                .run_if(input_just_pressed(KeyCode::Enter)),
-        )
-        .add_systems(
-            Update,
-            (
-                apply_velocity,
-                check_for_collisions,
-                check_for_game_over,
-                despawn_stones,
-                handle_score,
-                move_bat,
-                play_sounds,
-            )
-                .run_if(in_state(GameState::Game)),
+            (play_sounds.run_if(in_state(GameState::Game)), tint_stones).in_set(GameSet::Feedback),
         )
-        .add_message::<CollisionMessage>()
-        .init_state::<GameState>()
-        .init_resource::<Score>()
         .run();
 }
 
@@ -471,43 +1101,227 @@ mod tests {
     fn test_score_increased_only_for_stones() {
         // ...
         // example-end: 1
-        // example-start: 2 {0|1|3-5|7-8|10-14|15-16|all}
+        // example-start: 2 {0|1|3-7|9-10|12|13-14|all}
         let mut app = App::new();
 
         app.init_resource::<Score>()
+            .insert_resource(State::new(GameState::Game))
             .add_message::<CollisionMessage>()
-            .add_systems(Update, handle_score);
+            .add_systems(Update, check_for_collisions)
+            .add_observer(handle_score);
 
         app.update();
         assert_eq!(app.world().resource::<Score>().0, 0);
 
-        app.world_mut()
-            .resource_mut::<Messages<CollisionMessage>>()
-            .write(CollisionMessage {
-                obstacle: Obstacle::Bat,
-            });
+        ball_hits(app.world_mut(), Obstacle::Bat);
         app.update();
         assert_eq!(app.world().resource::<Score>().0, 0);
         // ...
         // example-end: 2
 
-        // example-start: 3 {2-8|10-16|all}
+        // example-start: 3 {2-4|6-8|all}
         // ...
-        app.world_mut()
-            .resource_mut::<Messages<CollisionMessage>>()
-            .write(CollisionMessage {
-                obstacle: Obstacle::Wall,
-            });
+        ball_hits(app.world_mut(), Obstacle::Wall);
         app.update();
         assert_eq!(app.world().resource::<Score>().0, 0);
 
-        app.world_mut()
-            .resource_mut::<Messages<CollisionMessage>>()
-            .write(CollisionMessage {
-                obstacle: Obstacle::Stone,
-            });
+        ball_hits(app.world_mut(), Obstacle::Stone);
         app.update();
         assert_eq!(app.world().resource::<Score>().0, 100);
         // example-end: 3
     }
+
+    // example-start: 4 {1-2|3-13|15-22|all}
+    // Spawns the obstacle next to the ones spawned before, together with a ball that hits it
+    fn ball_hits(world: &mut World, obstacle: Obstacle) -> Entity {
+        let position = Vec2::X * world.query::<&Collider>().iter(world).count() as f32 * 200.0;
+        let mut entity = world.spawn((
+            Transform::from_translation(position.extend(0.0)),
+            Collider {
+                size: Some(STONE_SIZE),
+                obstacle,
+            },
+        ));
+        if let Obstacle::Stone = obstacle {
+            entity.insert(Stone { hit_points: 1 });
+        }
+        let entity = entity.id();
+
+        // The ball moves up into the bottom side of the obstacle
+        let offset = Vec2::Y * (STONE_SIZE.y / 2.0 + BALL_RADIUS - 1.0);
+        world.spawn((
+            Transform::from_translation((position - offset).extend(0.0)),
+            Ball {
+                velocity: Vec2::new(0.0, BALL_SPEED),
+            },
+        ));
+        entity
+    }
+    // example-end: 4
+
+    // Not at the top of the module, which is shown in the presentation
+    use crate::testing::{spawn_ball_below, test_app};
+
+    #[test]
+    fn test_stone_hit_by_two_balls_is_scored_once() {
+        let mut app = test_app();
+
+        app.add_systems(Update, check_for_collisions)
+            .add_observer(handle_score);
+
+        let stone = ball_hits(app.world_mut(), Obstacle::Stone);
+        spawn_ball_below(app.world_mut(), Vec2::new(STONE_SIZE.x / 4.0, 0.0));
+        app.update();
+        assert_eq!(app.world().resource::<Score>().0, 100);
+        assert_eq!(app.world().get::<Stone>(stone).unwrap().hit_points, 0);
+    }
+
+    #[test]
+    fn test_tough_stone_is_scored_once_when_destroyed() {
+        let mut app = test_app();
+
+        app.add_systems(Update, check_for_collisions)
+            .add_observer(destroy_stone)
+            .add_observer(handle_score);
+
+        let stone = ball_hits(app.world_mut(), Obstacle::Stone);
+        app.world_mut().get_mut::<Stone>(stone).unwrap().hit_points = 2;
+        app.update();
+        assert_eq!(app.world().resource::<Score>().0, 0);
+
+        for _ in 0..2 {
+            spawn_ball_below(app.world_mut(), Vec2::ZERO);
+            app.update();
+            assert_eq!(app.world().resource::<Score>().0, 100);
+        }
+        assert!(app.world().get::<Destroyed>(stone).is_some());
+    }
+
+    #[test]
+    fn test_spawn_commands_use_placeholders_without_assets() {
+        let mut app = test_app();
+
+        app.world_mut().commands().queue(SpawnStone {
+            x: 0.0,
+            y: 0.0,
+            hit_points: 1,
+            kind: StoneKind::Normal,
+            moving: None,
+        });
+        app.world_mut().commands().queue(SpawnBat {
+            x: 0.0,
+            autopilot: None,
+        });
+        app.world_mut().flush();
+
+        let mut sprites = app.world_mut().query_filtered::<&Sprite, With<Collider>>();
+        let sizes: Vec<_> = sprites
+            .iter(app.world())
+            .map(|sprite| sprite.custom_size)
+            .collect();
+        assert_eq!(sizes.len(), 2);
+        assert!(sizes.contains(&Some(STONE_SIZE)));
+        assert!(sizes.contains(&Some(BAT_SIZE)));
+    }
+
+    #[test]
+    fn test_destroyed_stone_is_no_obstacle() {
+        let mut app = test_app();
+
+        app.add_systems(Update, check_for_collisions)
+            .add_observer(destroy_stone)
+            .add_observer(handle_score)
+            .init_resource::<DestroyedStones>()
+            .add_observer(
+                |_: On<StoneDestroyed>, mut count: ResMut<DestroyedStones>| {
+                    count.0 += 1;
+                },
+            );
+
+        let stone = app
+            .world_mut()
+            .spawn((
+                Transform::default(),
+                Collider {
+                    size: Some(STONE_SIZE),
+                    obstacle: Obstacle::Stone,
+                },
+                Stone { hit_points: 1 },
+            ))
+            .id();
+        // The ball overlaps the stone, and is pushed out of it by the first hit
+        let velocity = Vec2::new(0.0, BALL_SPEED);
+        let position = Vec3::new(0.0, -STONE_SIZE.y / 2.0, 0.0);
+        let ball = app
+            .world_mut()
+            .spawn((Transform::from_translation(position), Ball { velocity }))
+            .id();
+
+        app.update();
+        assert_eq!(app.world().resource::<Score>().0, 100);
+        assert_eq!(app.world().resource::<DestroyedStones>().0, 1);
+        assert!(app.world().get::<Destroyed>(stone).is_some());
+        assert!(app.world().get::<Collider>(stone).is_none());
+        assert_eq!(app.world().get::<Ball>(ball).unwrap().velocity, -velocity);
+        assert_ne!(
+            app.world().get::<Transform>(ball).unwrap().translation,
+            position
+        );
+
+        // Put back inside the destroyed stone, the ball passes through it
+        app.world_mut()
+            .entity_mut(ball)
+            .insert((Transform::from_translation(position), Ball { velocity }));
+        app.update();
+        assert_eq!(app.world().resource::<Score>().0, 100);
+        assert_eq!(app.world().resource::<DestroyedStones>().0, 1);
+        assert_eq!(app.world().get::<Ball>(ball).unwrap().velocity, velocity);
+        assert_eq!(
+            app.world().get::<Transform>(ball).unwrap().translation,
+            position
+        );
+    }
+
+    #[test]
+    fn test_ball_between_two_stones_is_reflected_once() {
+        let mut app = test_app();
+
+        app.add_systems(Update, check_for_collisions);
+
+        let stones = [-STONE_SIZE.x / 2.0, STONE_SIZE.x / 2.0].map(|x| {
+            app.world_mut()
+                .spawn((
+                    Transform::from_xyz(x, 0.0, 0.0),
+                    Collider {
+                        size: Some(STONE_SIZE),
+                        obstacle: Obstacle::Stone,
+                    },
+                    Stone { hit_points: 2 },
+                ))
+                .id()
+        });
+        // The ball touches the corner of the left stone and the bottom side of the right one
+        let velocity = Vec2::new(-0.6, 0.8) * BALL_SPEED;
+        let ball = app
+            .world_mut()
+            .spawn((
+                Transform::from_xyz(10.0, -STONE_SIZE.y / 2.0 - 6.0, 0.0),
+                Ball { velocity },
+            ))
+            .id();
+
+        app.update();
+        for stone in stones {
+            assert_eq!(app.world().get::<Stone>(stone).unwrap().hit_points, 1);
+        }
+
+        // Only the vertical movement is reflected, and the ball is pushed out of both stones
+        let new_velocity = app.world().get::<Ball>(ball).unwrap().velocity;
+        assert!((new_velocity - Vec2::new(velocity.x, -velocity.y)).length() < 0.01);
+        let position = app.world().get::<Transform>(ball).unwrap().translation;
+        assert!(position.y <= -STONE_SIZE.y / 2.0 - BALL_RADIUS + 0.01);
+    }
+
+    #[derive(Resource, Default)]
+    struct DestroyedStones(u32);
 }
@@ -0,0 +1,218 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+
+use crate::{GameSet, Playing};
+
+pub struct MovingObstaclesPlugin;
+
+impl Plugin for MovingObstaclesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            move_obstacles
+                .in_set(GameSet::Physics)
+                .run_if(in_state(Playing)),
+        );
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Reflect)]
+pub enum ObstaclePath {
+    // Moves back and forth horizontally, at most `extent` away from `center`
+    Patrol {
+        center: Vec2,
+        extent: f32,
+        speed: f32,
+    },
+    // Circles around `center`, starting at the angle `phase`
+    Orbit {
+        center: Vec2,
+        radius: f32,
+        phase: f32,
+        angular_speed: f32,
+    },
+}
+
+impl ObstaclePath {
+    pub fn position(&self, time: f32) -> Vec2 {
+        match *self {
+            ObstaclePath::Patrol {
+                center,
+                extent,
+                speed,
+            } => center + Vec2::X * extent * (time * speed / extent).sin(),
+            ObstaclePath::Orbit {
+                center,
+                radius,
+                phase,
+                angular_speed,
+            } => center + Vec2::from_angle(phase + time * angular_speed) * radius,
+        }
+    }
+
+    pub fn velocity(&self, time: f32) -> Vec2 {
+        match *self {
+            ObstaclePath::Patrol { extent, speed, .. } => {
+                Vec2::X * speed * (time * speed / extent).cos()
+            }
+            ObstaclePath::Orbit {
+                radius,
+                phase,
+                angular_speed,
+                ..
+            } => Vec2::from_angle(phase + time * angular_speed).perp() * radius * angular_speed,
+        }
+    }
+
+    // The same path mirrored at the vertical center line of the playfield
+    pub fn mirrored(&self) -> Self {
+        match *self {
+            ObstaclePath::Patrol {
+                center,
+                extent,
+                speed,
+            } => ObstaclePath::Patrol {
+                center: Vec2::new(-center.x, center.y),
+                extent,
+                speed: -speed,
+            },
+            ObstaclePath::Orbit {
+                center,
+                radius,
+                phase,
+                angular_speed,
+            } => ObstaclePath::Orbit {
+                center: Vec2::new(-center.x, center.y),
+                radius,
+                phase: (TAU / 2.0 - phase).rem_euclid(TAU),
+                angular_speed: -angular_speed,
+            },
+        }
+    }
+
+    // The area that an obstacle of `size` covers while it moves along the path
+    #[cfg(test)]
+    pub fn bounds(&self, size: Vec2) -> Rect {
+        let (center, reach) = match *self {
+            ObstaclePath::Patrol { center, extent, .. } => (center, Vec2::new(extent, 0.0)),
+            ObstaclePath::Orbit { center, radius, .. } => (center, Vec2::splat(radius)),
+        };
+
+        Rect::from_center_half_size(center, reach + size / 2.0)
+    }
+}
+
+// A stone or barrier that follows a path, so that it is harder to hit or to avoid
+#[derive(Clone, Copy, Component, Reflect)]
+#[reflect(Component)]
+pub struct MovingObstacle {
+    path: ObstaclePath,
+    time: f32,
+    velocity: Vec2,
+}
+
+impl MovingObstacle {
+    pub fn new(path: ObstaclePath) -> Self {
+        Self {
+            path,
+            time: 0.0,
+            velocity: path.velocity(0.0),
+        }
+    }
+
+    pub fn position(&self) -> Vec2 {
+        self.path.position(self.time)
+    }
+
+    // Used by the ball to bounce off correctly
+    pub fn velocity(&self) -> Vec2 {
+        self.velocity
+    }
+}
+
+fn move_obstacles(time: Res<Time>, mut obstacles: Query<(&mut MovingObstacle, &mut Transform)>) {
+    for (mut obstacle, mut transform) in &mut obstacles {
+        obstacle.time += time.delta_secs();
+        obstacle.velocity = obstacle.path.velocity(obstacle.time);
+
+        let position = obstacle.position();
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        check_for_collisions,
+        testing::{spawn_wall, test_app},
+        Ball, BALL_RADIUS, BALL_SPEED,
+    };
+
+    #[test]
+    fn test_path_stays_within_bounds() {
+        let paths = [
+            ObstaclePath::Patrol {
+                center: Vec2::new(100.0, -200.0),
+                extent: 300.0,
+                speed: 150.0,
+            },
+            ObstaclePath::Orbit {
+                center: Vec2::new(-400.0, -250.0),
+                radius: 80.0,
+                phase: 1.0,
+                angular_speed: 2.0,
+            },
+        ];
+
+        for path in paths {
+            let bounds = path.bounds(Vec2::ZERO);
+            let mirrored = path.mirrored();
+            for tick in 0..600 {
+                let time = tick as f32 / 60.0;
+                let position = path.position(time);
+                assert!(bounds.inflate(0.001).contains(position));
+
+                let mirrored_position = mirrored.position(time);
+                assert!((mirrored_position - Vec2::new(-position.x, position.y)).length() < 0.01);
+            }
+        }
+    }
+
+    #[test]
+    fn test_ball_bounces_off_moving_obstacle_with_its_velocity() {
+        let mut app = test_app();
+
+        app.add_systems(FixedUpdate, (move_obstacles, check_for_collisions).chain());
+
+        // A barrier that moves to the right faster than the ball touching its right side
+        let size = Vec2::new(200.0, 20.0);
+        let barrier = MovingObstacle::new(ObstaclePath::Patrol {
+            center: Vec2::ZERO,
+            extent: 400.0,
+            speed: 300.0,
+        });
+        let wall = spawn_wall(app.world_mut(), Vec2::ZERO, size);
+        app.world_mut().entity_mut(wall).insert(barrier);
+        let ball = app
+            .world_mut()
+            .spawn((
+                Transform::from_xyz(size.x / 2.0 + BALL_RADIUS - 1.0, 0.0, 0.0),
+                Ball {
+                    velocity: Vec2::new(0.2, -1.0).normalize() * BALL_SPEED,
+                },
+            ))
+            .id();
+
+        app.update();
+        app.update();
+
+        // The ball is pushed away instead of being overrun, without changing its speed
+        let velocity = app.world().get::<Ball>(ball).unwrap().velocity;
+        assert!(velocity.x > 300.0);
+        assert!(velocity.y < 0.0);
+        assert!((velocity.length() - BALL_SPEED).abs() < 0.01);
+    }
+}
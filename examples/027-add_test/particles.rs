@@ -0,0 +1,157 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+
+use crate::{CollisionMessage, GameSet, Obstacle, OnGameScreen, Playing, StoneDestroyed};
+
+// Upper limit for the number of particles alive at the same time
+const MAX_PARTICLES: usize = 400;
+const PARTICLE_SIZE: Vec2 = Vec2::new(6.0, 6.0);
+// The average colour of `sprites/stone-animated.png`, which is tinted by the sprite colour
+const STONE_TEXTURE_COLOR: Color = Color::srgb(0.78, 0.78, 0.75);
+const SPARK_COLOR: Color = Color::srgb(1.0, 0.85, 0.4);
+
+pub struct ParticlesPlugin;
+
+impl Plugin for ParticlesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (emit_particles, update_particles)
+                .in_set(GameSet::Feedback)
+                .run_if(in_state(Playing)),
+        )
+        .add_observer(emit_debris);
+    }
+}
+
+#[derive(Component)]
+pub struct Particle {
+    velocity: Vec2,
+    gravity: f32,
+    lifetime: Timer,
+}
+
+pub struct SpawnParticles {
+    pub position: Vec2,
+    // The main direction of the particles, `Vec2::ZERO` spreads them in all directions
+    pub direction: Vec2,
+    pub color: Color,
+    pub count: usize,
+    pub speed: f32,
+    pub gravity: f32,
+    pub lifetime: f32,
+}
+
+impl SpawnParticles {
+    fn debris(position: Vec2, color: Color) -> Self {
+        Self {
+            position,
+            direction: Vec2::ZERO,
+            color,
+            count: 24,
+            speed: 240.0,
+            gravity: 900.0,
+            lifetime: 0.8,
+        }
+    }
+
+    fn sparks(position: Vec2, direction: Vec2) -> Self {
+        Self {
+            position,
+            direction,
+            color: SPARK_COLOR,
+            count: 12,
+            speed: 420.0,
+            gravity: 300.0,
+            lifetime: 0.3,
+        }
+    }
+}
+
+impl Command for SpawnParticles {
+    type Out = ();
+
+    fn apply(self, world: &mut World) {
+        let alive = world.query::<&Particle>().iter(world).count();
+        let count = self.count.min(MAX_PARTICLES.saturating_sub(alive));
+
+        for i in 0..count {
+            // Spread the particles evenly and vary their speed, so that they do not move in lockstep
+            let fraction = i as f32 / self.count as f32;
+            let direction = if self.direction == Vec2::ZERO {
+                Vec2::from_angle(fraction * TAU)
+            } else {
+                Vec2::from_angle((fraction - 0.5) * 1.2).rotate(self.direction.normalize())
+            };
+            let speed = self.speed * (0.5 + 0.5 * ((i * 7) % 11) as f32 / 10.0);
+
+            world.spawn((
+                Sprite::from_color(self.color, PARTICLE_SIZE),
+                Transform::from_translation(self.position.extend(1.0)),
+                Particle {
+                    velocity: direction * speed,
+                    gravity: self.gravity,
+                    lifetime: Timer::from_seconds(self.lifetime, TimerMode::Once),
+                },
+                OnGameScreen,
+            ));
+        }
+    }
+}
+
+fn emit_particles(mut commands: Commands, mut collision_messages: MessageReader<CollisionMessage>) {
+    for message in collision_messages.read() {
+        if let Obstacle::Bat = message.obstacle {
+            commands.queue(SpawnParticles::sparks(message.point, message.side.normal()));
+        }
+    }
+}
+
+// The debris bursts out of the whole stone, not just the contact point
+fn emit_debris(
+    destroyed: On<StoneDestroyed>,
+    mut commands: Commands,
+    stones: Query<(&Sprite, &Transform)>,
+) {
+    if let Ok((sprite, transform)) = stones.get(destroyed.entity) {
+        commands.queue(SpawnParticles::debris(
+            transform.translation.truncate(),
+            tinted(STONE_TEXTURE_COLOR, sprite.color),
+        ));
+    }
+}
+
+fn update_particles(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut particles: Query<(Entity, &mut Particle, &mut Transform, &mut Sprite)>,
+) {
+    for (entity, mut particle, mut transform, mut sprite) in &mut particles {
+        particle.lifetime.tick(time.delta());
+        if particle.lifetime.is_finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        particle.velocity.y -= particle.gravity * time.delta_secs();
+        transform.translation += (particle.velocity * time.delta_secs()).extend(0.0);
+        sprite
+            .color
+            .set_alpha(particle.lifetime.fraction_remaining());
+    }
+}
+
+// Sprite colours are multiplied with the colour of the texture
+fn tinted(color: Color, tint: Color) -> Color {
+    let color = color.to_linear();
+    let tint = tint.to_linear();
+
+    LinearRgba::new(
+        color.red * tint.red,
+        color.green * tint.green,
+        color.blue * tint.blue,
+        color.alpha * tint.alpha,
+    )
+    .into()
+}
@@ -0,0 +1,185 @@
+use bevy::{
+    math::bounding::{Aabb2d, IntersectsVolume},
+    prelude::*,
+};
+
+use crate::{
+    laser::Laser, rng::GameRng, sticky::Sticky, Bat, GameSet, GameState, OnGameScreen,
+    StoneDestroyed, BAT_SIZE, MAX_Y,
+};
+
+// The chance that a destroyed stone drops a power-up
+const DROP_CHANCE: f32 = 0.1;
+const POWER_UP_SIZE: Vec2 = Vec2::new(48.0, 20.0);
+const FALL_SPEED: f32 = 240.0;
+const LASER_COLOR: Color = Color::srgb(1.0, 0.3, 0.3);
+const STICKY_COLOR: Color = Color::srgb(0.4, 0.9, 0.4);
+
+// Destroyed stones sometimes drop a power-up, which is activated when it is caught with the bat
+pub struct PowerUpPlugin;
+
+impl Plugin for PowerUpPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_observer(drop_power_up).add_systems(
+            FixedUpdate,
+            (
+                fall_power_ups.in_set(GameSet::Physics),
+                catch_power_ups.in_set(GameSet::Resolve),
+            )
+                .run_if(in_state(GameState::Game)),
+        );
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Reflect)]
+pub enum PowerUp {
+    Laser,
+    Sticky,
+}
+
+impl PowerUp {
+    const ALL: [PowerUp; 2] = [PowerUp::Laser, PowerUp::Sticky];
+
+    fn color(self) -> Color {
+        match self {
+            PowerUp::Laser => LASER_COLOR,
+            PowerUp::Sticky => STICKY_COLOR,
+        }
+    }
+
+    // Catching a power-up that is still active starts its time again
+    fn activate(self, bat: &mut EntityCommands) {
+        match self {
+            PowerUp::Laser => bat.insert(Laser::default()),
+            PowerUp::Sticky => bat.insert(Sticky::default()),
+        };
+    }
+}
+
+// A power-up on its way down to the bat
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Falling(pub PowerUp);
+
+pub struct SpawnPowerUp {
+    pub position: Vec2,
+    pub power_up: PowerUp,
+}
+
+impl Command for SpawnPowerUp {
+    type Out = ();
+
+    fn apply(self, world: &mut World) {
+        world.spawn((
+            Sprite::from_color(self.power_up.color(), POWER_UP_SIZE),
+            Transform::from_translation(self.position.extend(1.0)),
+            Falling(self.power_up),
+            OnGameScreen,
+        ));
+    }
+}
+
+// Only in a game, the demo on the title screen has no use for power-ups
+fn drop_power_up(
+    destroyed: On<StoneDestroyed>,
+    mut commands: Commands,
+    game_state: Res<State<GameState>>,
+    mut rng: ResMut<GameRng>,
+    stones: Query<&Transform>,
+) {
+    if *game_state.get() != GameState::Game || !rng.chance(DROP_CHANCE) {
+        return;
+    }
+
+    let power_up = PowerUp::ALL[(rng.f32() * PowerUp::ALL.len() as f32) as usize];
+    if let Ok(transform) = stones.get(destroyed.entity) {
+        commands.queue(SpawnPowerUp {
+            position: transform.translation.truncate(),
+            power_up,
+        });
+    }
+}
+
+fn fall_power_ups(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut power_ups: Query<(Entity, &mut Transform), With<Falling>>,
+) {
+    for (entity, mut transform) in &mut power_ups {
+        transform.translation.y -= FALL_SPEED * time.delta_secs();
+
+        if transform.translation.y < -MAX_Y / 2.0 {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn catch_power_ups(
+    mut commands: Commands,
+    power_ups: Query<(Entity, &Transform, &Falling)>,
+    bats: Query<(Entity, &Transform), With<Bat>>,
+) {
+    for (entity, transform, falling) in &power_ups {
+        let bounds = Aabb2d::new(transform.translation.truncate(), POWER_UP_SIZE / 2.0);
+        let catcher = bats.iter().find(|(_, bat)| {
+            Aabb2d::new(bat.translation.truncate(), BAT_SIZE / 2.0).intersects(&bounds)
+        });
+
+        if let Some((bat, _)) = catcher {
+            falling.0.activate(&mut commands.entity(bat));
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{headless::headless_app_with, Ball};
+
+    #[test]
+    fn test_power_up_is_activated_when_caught() {
+        let mut app = headless_app_with(|_| {});
+
+        // Without a ball, the game is not over before the power-ups have fallen down
+        let world = app.world_mut();
+        let balls: Vec<Entity> = world
+            .query_filtered::<Entity, With<Ball>>()
+            .iter(world)
+            .collect();
+        for ball in balls {
+            world.despawn(ball);
+        }
+
+        let mut bats = app
+            .world_mut()
+            .query_filtered::<(Entity, &Transform), With<Bat>>();
+        let (bat, transform) = bats.single(app.world()).unwrap();
+        let x = transform.translation.x;
+        assert!(app.world().get::<Laser>(bat).is_none());
+
+        // Two power-ups fall onto the bat, the third one misses it
+        let power_ups = [
+            (x, PowerUp::Laser),
+            (x, PowerUp::Sticky),
+            (x + BAT_SIZE.x + POWER_UP_SIZE.x, PowerUp::Laser),
+        ];
+        for (x, power_up) in power_ups {
+            app.world_mut().commands().queue(SpawnPowerUp {
+                position: Vec2::new(x, 0.0),
+                power_up,
+            });
+        }
+        app.world_mut().flush();
+
+        // Long enough for both to reach the bottom
+        for _ in 0..(60.0 * MAX_Y / FALL_SPEED) as usize {
+            app.update();
+        }
+
+        assert!(app.world().get::<Laser>(bat).is_some());
+        assert!(app.world().get::<Sticky>(bat).is_some());
+        let world = app.world_mut();
+        assert_eq!(world.query::<&Falling>().iter(world).count(), 0);
+    }
+}
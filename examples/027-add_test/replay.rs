@@ -0,0 +1,289 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+
+use crate::{
+    argument_value, move_bat,
+    rng::{seed_game, NextSeed},
+    save::{restore_game, SavedGame},
+    BatInput, GameSet, GameSetup, GameState,
+};
+
+const MAGIC: &[u8; 4] = b"BRKR";
+const VERSION: u8 = 2;
+const HEADER_SIZE: usize = MAGIC.len() + 1 + 8 + 4 + 4;
+// The bat movement and whether the mouse button was clicked
+const INPUT_SIZE: usize = 4 + 1;
+
+// Records the game with `--record <file>` and replays a recorded game with `--replay <file>`
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        if let Some(path) = argument_value("--record") {
+            app.insert_resource(Recorder::new(path.into()));
+        }
+
+        if let Some(path) = argument_value("--replay") {
+            match Replay::load(Path::new(&path)) {
+                Ok(replay) => {
+                    app.insert_resource(replay.setup)
+                        .insert_resource(NextSeed::Fixed(replay.setup.seed))
+                        .insert_resource(Playback::new(replay.inputs));
+                }
+                Err(error) => error!("Could not load replay {path}: {error}"),
+            }
+        }
+
+        app.add_systems(
+            OnEnter(GameState::Game),
+            (
+                // Records the seed of the new game, unless a saved game is about to be restored
+                start_recording
+                    .after(seed_game)
+                    .run_if(resource_exists::<Recorder>),
+                restart_playback.run_if(resource_exists::<Playback>),
+            )
+                .before(restore_game),
+        )
+        .add_systems(
+            OnExit(GameState::Game),
+            save_recording.run_if(resource_exists::<Recorder>),
+        )
+        .add_systems(
+            FixedUpdate,
+            (
+                play_back_input.run_if(resource_exists::<Playback>),
+                record_input.run_if(resource_exists::<Recorder>),
+            )
+                .chain()
+                .in_set(GameSet::Input)
+                .before(move_bat)
+                .run_if(in_state(GameState::Game)),
+        );
+    }
+}
+
+// The input of the player for every tick of a game, together with everything else that is
+// needed to reproduce the game exactly
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct Replay {
+    pub setup: GameSetup,
+    pub inputs: Vec<BatInput>,
+}
+
+impl Replay {
+    // The format is a small header followed by the input of every tick:
+    // magic, version, seed, level, number of ticks, inputs (all little endian)
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_SIZE + self.inputs.len() * INPUT_SIZE);
+
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&self.setup.seed.to_le_bytes());
+        bytes.extend_from_slice(&self.setup.level.to_le_bytes());
+        bytes.extend_from_slice(&(self.inputs.len() as u32).to_le_bytes());
+        for input in &self.inputs {
+            bytes.extend_from_slice(&input.movement.to_le_bytes());
+            bytes.push(input.click.into());
+        }
+
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        if bytes.len() < HEADER_SIZE || &bytes[0..4] != MAGIC {
+            return Err(invalid_data("not a replay file"));
+        }
+        if bytes[4] != VERSION {
+            return Err(invalid_data("unsupported replay version"));
+        }
+
+        let seed = u64::from_le_bytes(bytes[5..13].try_into().unwrap());
+        let level = u32::from_le_bytes(bytes[13..17].try_into().unwrap());
+        let ticks = u32::from_le_bytes(bytes[17..21].try_into().unwrap()) as usize;
+
+        let inputs = &bytes[HEADER_SIZE..];
+        if inputs.len() != ticks * INPUT_SIZE {
+            return Err(invalid_data("truncated replay file"));
+        }
+
+        Ok(Self {
+            setup: GameSetup { seed, level },
+            inputs: inputs
+                .chunks_exact(INPUT_SIZE)
+                .map(|chunk| BatInput {
+                    movement: f32::from_le_bytes(chunk[0..4].try_into().unwrap()),
+                    click: chunk[4] != 0,
+                })
+                .collect(),
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_bytes())
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Self::from_bytes(&fs::read(path)?)
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+// Records every new game to the same file. A continued game is not recorded, because it does
+// not start from its `GameSetup` and could not be replayed.
+#[derive(Resource)]
+pub struct Recorder {
+    path: PathBuf,
+    replay: Option<Replay>,
+}
+
+impl Recorder {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path, replay: None }
+    }
+}
+
+// Replaces the input of the player with the recorded input
+#[derive(Resource)]
+pub struct Playback {
+    inputs: Vec<BatInput>,
+    tick: usize,
+}
+
+impl Playback {
+    pub fn new(inputs: Vec<BatInput>) -> Self {
+        Self { inputs, tick: 0 }
+    }
+}
+
+fn start_recording(
+    mut recorder: ResMut<Recorder>,
+    setup: Res<GameSetup>,
+    saved_game: Option<Res<SavedGame>>,
+) {
+    recorder.replay = saved_game.is_none().then(|| Replay {
+        setup: *setup,
+        inputs: Vec::new(),
+    });
+}
+
+fn record_input(mut recorder: ResMut<Recorder>, bat_input: Res<BatInput>) {
+    if let Some(replay) = &mut recorder.replay {
+        replay.inputs.push(*bat_input);
+    }
+}
+
+fn save_recording(recorder: Res<Recorder>) {
+    let Some(replay) = &recorder.replay else {
+        return;
+    };
+
+    match replay.save(&recorder.path) {
+        Ok(()) => info!(
+            "Recorded {} ticks to {}",
+            replay.inputs.len(),
+            recorder.path.display()
+        ),
+        Err(error) => error!(
+            "Could not save replay to {}: {error}",
+            recorder.path.display()
+        ),
+    }
+}
+
+fn restart_playback(mut playback: ResMut<Playback>) {
+    playback.tick = 0;
+}
+
+// Once the recording ends, the bat stays where it is
+fn play_back_input(mut playback: ResMut<Playback>, mut bat_input: ResMut<BatInput>) {
+    *bat_input = playback
+        .inputs
+        .get(playback.tick)
+        .copied()
+        .unwrap_or_default();
+    playback.tick += 1;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{headless::headless_app_with, Ball, Score, Stone};
+
+    const TICKS: usize = 120;
+
+    // Moves the bat back and forth in an irregular way, clicking now and then
+    fn scripted_input(tick: usize) -> BatInput {
+        BatInput {
+            movement: (tick as f32 * 0.05).sin() * 25.0 + (tick % 7) as f32,
+            click: tick.is_multiple_of(40),
+        }
+    }
+
+    fn snapshot(app: &mut App) -> (Vec<Vec3>, Vec<Vec2>, u64, usize) {
+        let world = app.world_mut();
+        let mut balls = world.query::<(&Transform, &Ball)>();
+        let (positions, velocities) = balls
+            .iter(world)
+            .map(|(transform, ball)| (transform.translation, ball.velocity))
+            .unzip();
+        let stones = world.query::<&Stone>().iter(world).count();
+
+        (positions, velocities, world.resource::<Score>().0, stones)
+    }
+
+    #[test]
+    fn test_replay_survives_round_trip() {
+        let replay = Replay {
+            setup: GameSetup { seed: 42, level: 3 },
+            inputs: [(0.0, false), (1.5, true), (-2.25, false), (0.0, true)]
+                .map(|(movement, click)| BatInput { movement, click })
+                .to_vec(),
+        };
+
+        assert_eq!(Replay::from_bytes(&replay.to_bytes()).unwrap(), replay);
+        assert!(Replay::from_bytes(&replay.to_bytes()[..HEADER_SIZE + 3]).is_err());
+        assert!(Replay::from_bytes(b"not a replay at all").is_err());
+    }
+
+    #[test]
+    fn test_replay_reproduces_recorded_game() {
+        let mut recorded = headless_app_with(|app| {
+            app.insert_resource(Recorder::new(
+                std::env::temp_dir().join("breakout-test.replay"),
+            ));
+        });
+        for tick in 0..TICKS {
+            *recorded.world_mut().resource_mut::<BatInput>() = scripted_input(tick);
+            recorded.update();
+        }
+
+        let replay = recorded
+            .world()
+            .resource::<Recorder>()
+            .replay
+            .clone()
+            .unwrap();
+        assert_eq!(replay.inputs.len(), TICKS);
+
+        let mut replayed = headless_app_with(|app| {
+            app.insert_resource(replay.setup)
+                .insert_resource(NextSeed::Fixed(replay.setup.seed))
+                .insert_resource(Playback::new(replay.inputs.clone()));
+        });
+        for _ in 0..TICKS {
+            replayed.update();
+        }
+
+        let expected = snapshot(&mut recorded);
+        assert!(expected.2 > 0);
+        assert_eq!(snapshot(&mut replayed), expected);
+    }
+}
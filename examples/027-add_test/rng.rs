@@ -0,0 +1,136 @@
+use std::{
+    hash::{BuildHasher, RandomState},
+    ops::Range,
+};
+
+use bevy::prelude::*;
+
+use crate::{argument_value, GameSetup};
+
+// Seeds every game with `--seed <n>`, or with a new random seed otherwise
+pub struct RngPlugin;
+
+impl Plugin for RngPlugin {
+    fn build(&self, app: &mut App) {
+        let next_seed = match argument_value("--seed").map(|seed| seed.parse()) {
+            Some(Ok(seed)) => NextSeed::Fixed(seed),
+            Some(Err(error)) => {
+                error!("Invalid seed, using a random one instead: {error}");
+                NextSeed::Random
+            }
+            None => NextSeed::Random,
+        };
+
+        app.insert_resource(next_seed).init_resource::<GameRng>();
+    }
+}
+
+// Where the seed of the next game comes from
+#[derive(Clone, Copy, PartialEq, Debug, Resource)]
+pub enum NextSeed {
+    Random,
+    Fixed(u64),
+}
+
+// The only source of randomness in the game, so that a game can be reproduced from its seed.
+// SplitMix64 is small and gives the same numbers on every platform.
+#[derive(Default, Reflect, Resource)]
+#[reflect(Resource)]
+pub struct GameRng {
+    state: u64,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // Uniformly distributed in `0.0..1.0`
+    pub fn f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    pub fn range(&mut self, range: Range<f32>) -> f32 {
+        range.start + (range.end - range.start) * self.f32()
+    }
+
+    pub fn chance(&mut self, probability: f32) -> bool {
+        self.f32() < probability
+    }
+}
+
+// Chooses the seed of a new game and seeds the generator with it
+pub fn seed_game(
+    next_seed: Res<NextSeed>,
+    mut game_setup: ResMut<GameSetup>,
+    mut rng: ResMut<GameRng>,
+) {
+    game_setup.seed = match *next_seed {
+        NextSeed::Random => RandomState::new().hash_one(()),
+        NextSeed::Fixed(seed) => seed,
+    };
+    *rng = GameRng::new(game_setup.seed);
+
+    info!("Starting a game with seed {}", game_setup.seed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{headless::headless_app_with, Ball, GameState, LastSeed, Score};
+
+    fn launch_velocity(seed: u64) -> Vec2 {
+        let mut app = headless_app_with(|app| {
+            app.insert_resource(NextSeed::Fixed(seed));
+        });
+
+        let mut balls = app.world_mut().query::<&Ball>();
+        balls.single(app.world()).unwrap().velocity
+    }
+
+    #[test]
+    fn test_same_seed_gives_same_numbers() {
+        let mut first = GameRng::new(42);
+        let mut second = GameRng::new(42);
+        let mut other = GameRng::new(43);
+
+        for _ in 0..100 {
+            let number = first.next_u64();
+            assert_eq!(number, second.next_u64());
+            assert_ne!(number, other.next_u64());
+            assert!((0.0..1.0).contains(&first.f32()));
+            second.f32();
+            other.f32();
+        }
+    }
+
+    #[test]
+    fn test_seed_determines_ball_launch() {
+        assert_eq!(launch_velocity(7), launch_velocity(7));
+        assert_ne!(launch_velocity(7), launch_velocity(8));
+    }
+
+    #[test]
+    fn test_seed_of_game_without_points_is_remembered() {
+        let mut app = headless_app_with(|app| {
+            app.insert_resource(NextSeed::Fixed(7));
+        });
+        assert!(app.world().get_resource::<LastSeed>().is_none());
+
+        app.world_mut()
+            .resource_mut::<NextState<GameState>>()
+            .set(GameState::Title);
+        app.update();
+
+        assert_eq!(app.world().resource::<Score>().0, 0);
+        assert_eq!(app.world().resource::<LastSeed>().0, 7);
+    }
+}
@@ -0,0 +1,180 @@
+use bevy::prelude::*;
+
+use crate::{
+    save::{restore_game, SavedGame},
+    setup_game, Collider, CollisionMessage, GameSet, GameState, Obstacle, OnGameScreen,
+    WallLocation,
+};
+
+const SAFETY_NET_COLOR: Color = Color::srgb(0.4, 0.9, 0.6);
+const SAFETY_NET_BOUNCES: u32 = 3;
+const SAFETY_NET_DURATION: f32 = 30.0;
+// The safety net flashes during its last seconds and before its last bounce
+const FLASH_DURATION: f32 = 3.0;
+const FLASHES_PER_SECOND: f32 = 4.0;
+
+// Assist mode with `--assist`: every game starts with the bottom wall of the early examples,
+// which catches the ball a few times or for a while before it disappears
+pub struct SafetyNetPlugin;
+
+impl Plugin for SafetyNetPlugin {
+    fn build(&self, app: &mut App) {
+        if std::env::args().any(|arg| arg == "--assist") {
+            app.insert_resource(AssistMode);
+        }
+
+        app.add_systems(
+            OnEnter(GameState::Game),
+            // A continued game only gets the safety net that was saved with it
+            spawn_assist_safety_net
+                .after(setup_game)
+                .before(restore_game)
+                .run_if(resource_exists::<AssistMode>)
+                .run_if(not(resource_exists::<SavedGame>)),
+        )
+        .add_systems(
+            FixedUpdate,
+            wear_out_safety_nets
+                .in_set(GameSet::Cleanup)
+                .run_if(in_state(GameState::Game)),
+        )
+        .add_systems(Update, flash_safety_nets.in_set(GameSet::Feedback));
+    }
+}
+
+#[derive(Resource)]
+pub struct AssistMode;
+
+// A bottom wall that disappears after a number of bounces or when its time is up
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct SafetyNet {
+    pub bounces: u32,
+    pub timer: Timer,
+}
+
+impl SafetyNet {
+    // Whether the safety net is about to disappear
+    fn is_ending(&self) -> bool {
+        self.bounces <= 1 || self.timer.remaining_secs() < FLASH_DURATION
+    }
+}
+
+pub struct SpawnSafetyNet {
+    pub bounces: u32,
+    pub duration: f32,
+}
+
+impl Default for SpawnSafetyNet {
+    fn default() -> Self {
+        Self {
+            bounces: SAFETY_NET_BOUNCES,
+            duration: SAFETY_NET_DURATION,
+        }
+    }
+}
+
+impl Command for SpawnSafetyNet {
+    type Out = ();
+
+    fn apply(self, world: &mut World) {
+        let location = WallLocation::Bottom;
+        world.spawn((
+            Sprite::from_color(SAFETY_NET_COLOR, Vec2::ONE),
+            Transform::from_translation(location.position()).with_scale(location.size()),
+            Collider {
+                size: None,
+                obstacle: Obstacle::SafetyNet,
+            },
+            SafetyNet {
+                bounces: self.bounces,
+                timer: Timer::from_seconds(self.duration, TimerMode::Once),
+            },
+            OnGameScreen,
+        ));
+    }
+}
+
+fn spawn_assist_safety_net(mut commands: Commands) {
+    commands.queue(SpawnSafetyNet::default());
+}
+
+fn wear_out_safety_nets(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut collision_messages: MessageReader<CollisionMessage>,
+    mut safety_nets: Query<(Entity, &mut SafetyNet)>,
+) {
+    for message in collision_messages.read() {
+        if let Ok((_, mut safety_net)) = safety_nets.get_mut(message.obstacle_entity) {
+            safety_net.bounces = safety_net.bounces.saturating_sub(1);
+        }
+    }
+
+    for (entity, mut safety_net) in &mut safety_nets {
+        safety_net.timer.tick(time.delta());
+        if safety_net.bounces == 0 || safety_net.timer.is_finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn flash_safety_nets(time: Res<Time>, mut safety_nets: Query<(&SafetyNet, &mut Sprite)>) {
+    for (safety_net, mut sprite) in &mut safety_nets {
+        let visible =
+            !safety_net.is_ending() || (time.elapsed_secs() * FLASHES_PER_SECOND).fract() < 0.5;
+        sprite.color.set_alpha(if visible { 1.0 } else { 0.2 });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{headless::headless_app_with, Ball, BALL_SPEED, MAX_Y};
+
+    #[test]
+    fn test_safety_net_catches_the_ball_until_it_is_worn_out() {
+        let mut app = headless_app_with(|app| {
+            app.insert_resource(AssistMode);
+        });
+
+        let mut safety_nets = app.world_mut().query::<&SafetyNet>();
+        assert_eq!(safety_nets.iter(app.world()).count(), 1);
+
+        // The ball falls straight down, missing the bat, and bounces off the safety net
+        let mut balls = app.world_mut().query::<(&mut Ball, &mut Transform)>();
+        let (mut ball, mut transform) = balls.single_mut(app.world_mut()).unwrap();
+        ball.velocity = Vec2::new(0.0, -BALL_SPEED);
+        transform.translation = Vec3::new(600.0, -MAX_Y / 4.0, 0.0);
+
+        for _ in 0..60 {
+            app.update();
+        }
+
+        let velocity = balls.single(app.world()).unwrap().0.velocity;
+        assert!(velocity.y > 0.0);
+        let safety_net = safety_nets.single(app.world()).unwrap();
+        assert_eq!(safety_net.bounces, SAFETY_NET_BOUNCES - 1);
+
+        // The last bounce still catches the ball, but then the safety net disappears
+        app.world_mut()
+            .query::<&mut SafetyNet>()
+            .single_mut(app.world_mut())
+            .unwrap()
+            .bounces = 1;
+        for _ in 0..2 {
+            let (mut ball, _) = balls.single_mut(app.world_mut()).unwrap();
+            ball.velocity = Vec2::new(0.0, -BALL_SPEED);
+
+            for _ in 0..60 {
+                app.update();
+            }
+        }
+
+        assert_eq!(safety_nets.iter(app.world()).count(), 0);
+        assert_eq!(
+            *app.world().resource::<State<GameState>>(),
+            GameState::Title
+        );
+    }
+}
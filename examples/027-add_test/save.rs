@@ -0,0 +1,705 @@
+use std::{
+    any::TypeId,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use bevy::{
+    asset::{AssetPath, LoadFromPath, UntypedHandle},
+    ecs::entity::EntityHashMap,
+    input::common_conditions::input_just_pressed,
+    prelude::*,
+    reflect::TypeRegistry,
+    world_serialization::{
+        serde::WorldDeserializer, DynamicWorld, DynamicWorldBuilder, WorldInstanceSpawnError,
+    },
+};
+use serde::de::DeserializeSeed;
+
+use crate::{
+    explosion::{Explosive, Fuse, SpawnFuse},
+    laser::Laser,
+    level::StoneKind,
+    logic::{Gate, Hidden, Key, Lock, SpawnGate, SpawnLock, SpawnSwitch, Switch},
+    moving::MovingObstacle,
+    power_ups::{Falling, SpawnPowerUp},
+    rng::GameRng,
+    safety_net::{SafetyNet, SpawnSafetyNet},
+    setup_game, spawn_walls,
+    sticky::{Sticky, Stuck},
+    Ball, Barrier, Bat, BatInput, Combo, Destroyed, GameSetup, GameState, Score, SpawnBall,
+    SpawnBarrier, SpawnBat, SpawnStone, Stone,
+};
+
+// Saves the game with Escape or when the window is closed, so that it can be continued later
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Ball>()
+            .register_type::<Bat>()
+            .register_type::<Stone>()
+            .register_type::<Barrier>()
+            .register_type::<Explosive>()
+            .register_type::<Key>()
+            .register_type::<Hidden>()
+            .register_type::<Switch>()
+            .register_type::<Gate>()
+            .register_type::<Lock>()
+            .register_type::<MovingObstacle>()
+            .register_type::<Laser>()
+            .register_type::<Sticky>()
+            .register_type::<Stuck>()
+            .register_type::<SafetyNet>()
+            .register_type::<Fuse>()
+            .register_type::<Falling>()
+            .register_type::<Transform>()
+            .register_type::<Score>()
+            .register_type::<GameSetup>()
+            .register_type::<GameRng>()
+            .init_resource::<SaveFile>()
+            .add_systems(
+                OnEnter(GameState::Game),
+                // `setup_game` is skipped as long as the saved game has not been restored
+                restore_game
+                    .after(setup_game)
+                    .run_if(resource_exists::<SavedGame>),
+            )
+            .add_systems(
+                Update,
+                (save_game, quit_to_title)
+                    .chain()
+                    .run_if(in_state(GameState::Game))
+                    .run_if(input_just_pressed(KeyCode::Escape)),
+            )
+            .add_systems(
+                Update,
+                continue_game
+                    .run_if(in_state(GameState::Title))
+                    .run_if(input_just_pressed(KeyCode::KeyC)),
+            )
+            .add_systems(
+                Last,
+                save_game
+                    .run_if(in_state(GameState::Game))
+                    .run_if(on_message::<AppExit>),
+            );
+    }
+}
+
+// The scene file in which the balls, the bat with its power-ups, the remaining stones and other
+// obstacles, the safety net, the falling power-ups, the pending explosions and the score are saved
+#[derive(Resource)]
+pub struct SaveFile(pub PathBuf);
+
+impl SaveFile {
+    pub fn exists(&self) -> bool {
+        self.0.exists()
+    }
+}
+
+impl Default for SaveFile {
+    fn default() -> Self {
+        Self(PathBuf::from("breakout.scn.ron"))
+    }
+}
+
+// A saved game, which replaces the new playfield when the game starts
+#[derive(Resource)]
+pub struct SavedGame {
+    // The balls, and the bat of the saved game that a ball is stuck to
+    balls: Vec<(SpawnBall, Option<Stuck>)>,
+    // The bats with their entity in the saved game and their power-ups
+    bats: Vec<(Entity, SpawnBat, Option<Laser>, Option<Sticky>)>,
+    stones: Vec<SpawnStone>,
+    barriers: Vec<SpawnBarrier>,
+    switches: Vec<SpawnSwitch>,
+    gates: Vec<SpawnGate>,
+    locks: Vec<SpawnLock>,
+    safety_nets: Vec<SpawnSafetyNet>,
+    power_up_drops: Vec<SpawnPowerUp>,
+    fuses: Vec<SpawnFuse>,
+    score: u64,
+    setup: GameSetup,
+    rng: GameRng,
+}
+
+impl SavedGame {
+    // The saved entities only contain the state of the game and no sprites or colliders,
+    // so they are read from a world of their own and spawned again like in a new game
+    fn from_scene(
+        scene: &DynamicWorld,
+        registry: AppTypeRegistry,
+    ) -> Result<Self, WorldInstanceSpawnError> {
+        let mut world = World::new();
+        world.insert_resource(registry);
+        scene.write_to_world(&mut world, &mut default())?;
+
+        let mut balls = world.query::<(&Transform, &Ball, Option<&Stuck>)>();
+        let mut bats = world
+            .query_filtered::<(Entity, &Transform, Option<&Laser>, Option<&Sticky>), With<Bat>>();
+        let mut stones = world.query::<(Entity, &Transform, &Stone, Option<&MovingObstacle>)>();
+        let mut barriers = world.query::<(&Barrier, &MovingObstacle)>();
+        let mut switches = world.query_filtered::<&Transform, With<Switch>>();
+        let mut gates = world.query::<(&Transform, &Gate)>();
+        let mut locks = world.query_filtered::<&Transform, With<Lock>>();
+        let mut safety_nets = world.query::<&SafetyNet>();
+        let mut power_up_drops = world.query::<(&Transform, &Falling)>();
+        let mut fuses = world.query::<&Fuse>();
+
+        Ok(Self {
+            balls: balls
+                .iter(&world)
+                .map(|(transform, ball, stuck)| {
+                    let ball = SpawnBall {
+                        position: transform.translation.truncate(),
+                        velocity: ball.velocity,
+                    };
+                    (ball, stuck.cloned())
+                })
+                .collect(),
+            bats: bats
+                .iter(&world)
+                .map(|(entity, transform, laser, sticky)| {
+                    let bat = SpawnBat {
+                        x: transform.translation.x,
+                        autopilot: None,
+                    };
+                    (entity, bat, laser.cloned(), sticky.cloned())
+                })
+                .collect(),
+            stones: stones
+                .iter(&world)
+                .map(|(entity, transform, stone, moving)| SpawnStone {
+                    x: transform.translation.x,
+                    y: transform.translation.y,
+                    hit_points: stone.hit_points,
+                    kind: saved_stone_kind(&world, entity),
+                    moving: moving.copied(),
+                })
+                .collect(),
+            barriers: barriers
+                .iter(&world)
+                .map(|(barrier, moving)| SpawnBarrier {
+                    size: barrier.size,
+                    moving: *moving,
+                })
+                .collect(),
+            switches: switches
+                .iter(&world)
+                .map(|transform| SpawnSwitch {
+                    position: transform.translation.truncate(),
+                })
+                .collect(),
+            gates: gates
+                .iter(&world)
+                .map(|(transform, gate)| SpawnGate {
+                    position: transform.translation.truncate(),
+                    size: gate.size,
+                    open: gate.open,
+                })
+                .collect(),
+            locks: locks
+                .iter(&world)
+                .map(|transform| SpawnLock {
+                    position: transform.translation.truncate(),
+                })
+                .collect(),
+            // The safety net only lasts as long as it had left
+            safety_nets: safety_nets
+                .iter(&world)
+                .map(|safety_net| SpawnSafetyNet {
+                    bounces: safety_net.bounces,
+                    duration: safety_net.timer.remaining_secs(),
+                })
+                .collect(),
+            power_up_drops: power_up_drops
+                .iter(&world)
+                .map(|(transform, falling)| SpawnPowerUp {
+                    position: transform.translation.truncate(),
+                    power_up: falling.0,
+                })
+                .collect(),
+            // Chain reactions go on where they were interrupted
+            fuses: fuses
+                .iter(&world)
+                .map(|fuse| SpawnFuse {
+                    position: fuse.position,
+                    duration: fuse.timer.remaining_secs(),
+                })
+                .collect(),
+            score: world.get_resource::<Score>().map_or(0, |score| score.0),
+            setup: world
+                .get_resource::<GameSetup>()
+                .copied()
+                .unwrap_or_default(),
+            rng: world.remove_resource::<GameRng>().unwrap_or_default(),
+        })
+    }
+}
+
+// Tough stones are saved with their hit points, so only the marker components are needed
+fn saved_stone_kind(world: &World, entity: Entity) -> StoneKind {
+    let stone = world.entity(entity);
+    if stone.contains::<Explosive>() {
+        StoneKind::Explosive
+    } else if stone.contains::<Key>() {
+        StoneKind::Key
+    } else if stone.contains::<Hidden>() {
+        StoneKind::Hidden
+    } else {
+        StoneKind::Normal
+    }
+}
+
+fn save_game(world: &mut World) {
+    // Destroyed stones are not saved
+    let mut entities = world.query_filtered::<Entity, (
+        Or<(
+            With<Ball>,
+            With<Bat>,
+            With<Stone>,
+            With<Barrier>,
+            With<Switch>,
+            With<Gate>,
+            With<Lock>,
+            With<SafetyNet>,
+            With<Falling>,
+            With<Fuse>,
+        )>,
+        Without<Destroyed>,
+    )>();
+
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    let scene = DynamicWorldBuilder::from_world(world, &registry.read())
+        .deny_all()
+        .allow_component::<Ball>()
+        .allow_component::<Bat>()
+        .allow_component::<Stone>()
+        .allow_component::<Barrier>()
+        .allow_component::<Explosive>()
+        .allow_component::<Key>()
+        .allow_component::<Hidden>()
+        .allow_component::<Switch>()
+        .allow_component::<Gate>()
+        .allow_component::<Lock>()
+        .allow_component::<MovingObstacle>()
+        .allow_component::<Laser>()
+        .allow_component::<Sticky>()
+        .allow_component::<Stuck>()
+        .allow_component::<SafetyNet>()
+        .allow_component::<Falling>()
+        .allow_component::<Fuse>()
+        .allow_component::<Transform>()
+        .allow_resource::<Score>()
+        .allow_resource::<GameSetup>()
+        .allow_resource::<GameRng>()
+        .extract_entities(entities.iter(world))
+        .extract_resources()
+        .build();
+
+    let path = &world.resource::<SaveFile>().0;
+    match scene.serialize(&world.resource::<AppTypeRegistry>().read()) {
+        Ok(serialized) => match fs::write(path, serialized) {
+            Ok(()) => info!("Saved the game to {}", path.display()),
+            Err(error) => error!("Could not save the game to {}: {error}", path.display()),
+        },
+        Err(error) => error!("Could not serialize the game: {error}"),
+    }
+}
+
+fn quit_to_title(mut game_state: ResMut<NextState<GameState>>) {
+    game_state.set(GameState::Title);
+}
+
+// A saved game can only be continued once
+fn continue_game(
+    mut commands: Commands,
+    save_file: Res<SaveFile>,
+    registry: Res<AppTypeRegistry>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    if !save_file.exists() {
+        return;
+    }
+
+    let scene = match load_scene(&save_file.0, &registry.read()) {
+        Ok(scene) => scene,
+        Err(error) => {
+            error!("Could not load the saved game: {error}");
+            return;
+        }
+    };
+
+    match SavedGame::from_scene(&scene, registry.clone()) {
+        Ok(saved_game) => {
+            if let Err(error) = fs::remove_file(&save_file.0) {
+                warn!("Could not remove the saved game: {error}");
+            }
+
+            commands.insert_resource(saved_game);
+            game_state.set(GameState::Game);
+        }
+        Err(error) => error!("Could not restore the saved game: {error}"),
+    }
+}
+
+fn load_scene(path: &Path, registry: &TypeRegistry) -> io::Result<DynamicWorld> {
+    let serialized = fs::read_to_string(path)?;
+    let mut deserializer = ron::de::Deserializer::from_str(&serialized)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+
+    WorldDeserializer {
+        type_registry: registry,
+        load_from_path: &mut NoAssets,
+    }
+    .deserialize(&mut deserializer)
+    .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+}
+
+// The saved game refers to no assets, so no handles have to be loaded while it is read
+struct NoAssets;
+
+impl LoadFromPath for NoAssets {
+    fn load_from_path_erased(
+        &mut self,
+        type_id: TypeId,
+        _path: AssetPath<'static>,
+    ) -> UntypedHandle {
+        UntypedHandle::default_for_type(type_id)
+    }
+}
+
+pub fn restore_game(world: &mut World) {
+    let Some(saved_game) = world.remove_resource::<SavedGame>() else {
+        return;
+    };
+
+    world.insert_resource(Score(saved_game.score));
+    world.insert_resource(saved_game.setup);
+    world.insert_resource(saved_game.rng);
+    world.insert_resource(Combo::default());
+    world.insert_resource(BatInput::default());
+
+    // `Stuck` refers to the bats of the saved game, which are mapped to the ones spawned for it
+    let mut bats = EntityHashMap::default();
+    for (saved_bat, bat, laser, sticky) in saved_game.bats {
+        let bat = bat.spawn(world);
+        if let Some(laser) = laser {
+            world.entity_mut(bat).insert(laser);
+        }
+        if let Some(sticky) = sticky {
+            world.entity_mut(bat).insert(sticky);
+        }
+        bats.insert(saved_bat, bat);
+    }
+    for (ball, stuck) in saved_game.balls {
+        let ball = ball.spawn(world);
+        let stuck = stuck.and_then(|stuck| {
+            Some(Stuck {
+                bat: *bats.get(&stuck.bat)?,
+                ..stuck
+            })
+        });
+        if let Some(stuck) = stuck {
+            world.entity_mut(ball).insert(stuck);
+        }
+    }
+
+    spawn_walls(&mut world.commands());
+    for stone in saved_game.stones {
+        world.commands().queue(stone);
+    }
+    for barrier in saved_game.barriers {
+        world.commands().queue(barrier);
+    }
+    for switch in saved_game.switches {
+        world.commands().queue(switch);
+    }
+    for gate in saved_game.gates {
+        world.commands().queue(gate);
+    }
+    for lock in saved_game.locks {
+        world.commands().queue(lock);
+    }
+    for safety_net in saved_game.safety_nets {
+        world.commands().queue(safety_net);
+    }
+    for power_up in saved_game.power_up_drops {
+        world.commands().queue(power_up);
+    }
+    for fuse in saved_game.fuses {
+        world.commands().queue(fuse);
+    }
+    world.flush();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        headless::headless_app_with, power_ups::PowerUp, replay::Recorder, safety_net::AssistMode,
+    };
+
+    const TICKS: usize = 90;
+
+    fn snapshot(app: &mut App) -> (Vec<Vec3>, Vec<Vec2>, Vec<Vec3>, u64) {
+        let world = app.world_mut();
+        let mut balls = world.query::<(&Transform, &Ball)>();
+        let (positions, velocities) = balls
+            .iter(world)
+            .map(|(transform, ball)| (transform.translation, ball.velocity))
+            .unzip();
+        let mut stones = world.query_filtered::<&Transform, (With<Stone>, Without<Destroyed>)>();
+        let mut stones: Vec<_> = stones
+            .iter(world)
+            .map(|transform| transform.translation)
+            .collect();
+        stones.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+
+        (positions, velocities, stones, world.resource::<Score>().0)
+    }
+
+    // Continues the game saved to `path` in a new app, like from the title screen
+    fn continue_saved_game(path: &Path) -> App {
+        continue_saved_game_with(path, |_| {})
+    }
+
+    // Like `continue_saved_game`, but `configure` can change the new app before the game starts
+    fn continue_saved_game_with(path: &Path, configure: impl FnOnce(&mut App)) -> App {
+        let mut app = headless_app_with(|app| {
+            app.insert_resource(SaveFile(path.to_path_buf()));
+            configure(app);
+        });
+        app.world_mut()
+            .resource_mut::<NextState<GameState>>()
+            .set(GameState::Title);
+        app.update();
+        app.world_mut().run_system_cached(continue_game).unwrap();
+        app.update();
+        assert_eq!(*app.world().resource::<State<GameState>>(), GameState::Game);
+        assert!(!path.exists());
+
+        app
+    }
+
+    // The remaining time of the power-ups and the safety net, and the stuck balls
+    #[derive(PartialEq, Debug)]
+    struct Timers {
+        lasers: Vec<f32>,
+        stickies: Vec<f32>,
+        safety_nets: Vec<(u32, f32)>,
+        stuck_balls: Vec<(f32, f32)>,
+    }
+
+    fn timers(app: &mut App) -> Timers {
+        let world = app.world_mut();
+        let mut stuck_balls = world.query::<&Stuck>();
+        let stuck_balls = stuck_balls
+            .iter(world)
+            .map(|stuck| {
+                assert!(world.get::<Bat>(stuck.bat).is_some());
+                (stuck.offset, stuck.timer.remaining_secs())
+            })
+            .collect();
+
+        Timers {
+            lasers: world
+                .query::<&Laser>()
+                .iter(world)
+                .map(|laser| laser.timer.remaining_secs())
+                .collect(),
+            stickies: world
+                .query::<&Sticky>()
+                .iter(world)
+                .map(|sticky| sticky.timer.remaining_secs())
+                .collect(),
+            safety_nets: world
+                .query::<&SafetyNet>()
+                .iter(world)
+                .map(|safety_net| (safety_net.bounces, safety_net.timer.remaining_secs()))
+                .collect(),
+            stuck_balls,
+        }
+    }
+
+    #[test]
+    fn test_saved_game_is_continued() {
+        let path = std::env::temp_dir().join("breakout-test.scn.ron");
+        let mut original = headless_app_with(|app| {
+            app.insert_resource(SaveFile(path.clone()));
+        });
+        for _ in 0..TICKS {
+            original.update();
+        }
+        original.world_mut().run_system_cached(save_game).unwrap();
+        assert!(path.exists());
+
+        let mut continued = continue_saved_game(&path);
+
+        // The continued game has played one tick since it was restored
+        original.update();
+        let expected = snapshot(&mut original);
+        assert!(expected.3 > 0);
+        assert_eq!(snapshot(&mut continued), expected);
+    }
+
+    #[test]
+    fn test_power_ups_and_safety_net_are_continued() {
+        let path = std::env::temp_dir().join("breakout-power-ups-test.scn.ron");
+        let mut original = headless_app_with(|app| {
+            app.insert_resource(SaveFile(path.clone()));
+        });
+
+        // The ball rests on the sticky bat, which also has the laser, above a safety net
+        let world = original.world_mut();
+        let bat = world
+            .query_filtered::<Entity, With<Bat>>()
+            .single(world)
+            .unwrap();
+        let ball = world
+            .query_filtered::<Entity, With<Ball>>()
+            .single(world)
+            .unwrap();
+        world
+            .entity_mut(bat)
+            .insert((Laser::default(), Sticky::default()));
+        world.entity_mut(ball).insert(Stuck {
+            bat,
+            offset: 20.0,
+            timer: Timer::from_seconds(2.0, TimerMode::Once),
+        });
+        world.commands().queue(SpawnSafetyNet::default());
+        world.flush();
+        for _ in 0..TICKS {
+            original.update();
+        }
+        original.world_mut().run_system_cached(save_game).unwrap();
+        // In assist mode, the continued game does not get another safety net
+        let mut continued = continue_saved_game_with(&path, |app| {
+            app.insert_resource(AssistMode);
+        });
+
+        original.update();
+        let expected = timers(&mut original);
+        assert_eq!(expected.stuck_balls.len(), 1);
+        assert_eq!(expected.safety_nets.len(), 1);
+        assert_eq!(timers(&mut continued), expected);
+        assert_eq!(snapshot(&mut continued), snapshot(&mut original));
+    }
+
+    // The positions of the falling power-ups and the pending explosions with their remaining time
+    fn drops_and_fuses(app: &mut App) -> (Vec<Vec3>, Vec<(Vec2, f32)>) {
+        let world = app.world_mut();
+        let drops = world
+            .query_filtered::<&Transform, With<Falling>>()
+            .iter(world)
+            .map(|transform| transform.translation)
+            .collect();
+        let fuses = world
+            .query::<&Fuse>()
+            .iter(world)
+            .map(|fuse| (fuse.position, fuse.timer.remaining_secs()))
+            .collect();
+
+        (drops, fuses)
+    }
+
+    #[test]
+    fn test_power_ups_stay_with_their_bat_at_the_same_position() {
+        let path = std::env::temp_dir().join("breakout-same-position-test.scn.ron");
+        let mut original = headless_app_with(|app| {
+            app.insert_resource(SaveFile(path.clone()));
+        });
+
+        // A second bat with the laser covers the first one, which holds the ball
+        let world = original.world_mut();
+        let bat = world
+            .query_filtered::<Entity, With<Bat>>()
+            .single(world)
+            .unwrap();
+        let ball = world
+            .query_filtered::<Entity, With<Ball>>()
+            .single(world)
+            .unwrap();
+        let x = world.get::<Transform>(bat).unwrap().translation.x;
+        let laser_bat = SpawnBat { x, autopilot: None }.spawn(world);
+        world.entity_mut(laser_bat).insert(Laser::default());
+        world.entity_mut(ball).insert(Stuck {
+            bat,
+            offset: 0.0,
+            timer: Timer::from_seconds(2.0, TimerMode::Once),
+        });
+        original.world_mut().run_system_cached(save_game).unwrap();
+        let mut continued = continue_saved_game(&path);
+
+        let world = continued.world_mut();
+        let bats: Vec<_> = world
+            .query_filtered::<Has<Laser>, With<Bat>>()
+            .iter(world)
+            .collect();
+        assert_eq!(bats.len(), 2);
+        assert_eq!(bats.iter().filter(|&&laser| laser).count(), 1);
+        let stuck = world.query::<&Stuck>().single(world).unwrap();
+        assert!(world.get::<Bat>(stuck.bat).is_some());
+        assert!(world.get::<Laser>(stuck.bat).is_none());
+    }
+
+    #[test]
+    fn test_falling_power_ups_and_explosions_are_continued() {
+        let path = std::env::temp_dir().join("breakout-explosions-test.scn.ron");
+        let replay_path = std::env::temp_dir().join("breakout-explosions-test.replay");
+        let mut original = headless_app_with(|app| {
+            app.insert_resource(SaveFile(path.clone()));
+        });
+
+        // An explosion is about to go off among the stones, while a power-up falls down
+        let world = original.world_mut();
+        let stone = world
+            .query_filtered::<&Transform, With<Stone>>()
+            .iter(world)
+            .next()
+            .unwrap()
+            .translation
+            .truncate();
+        world.commands().queue(SpawnFuse {
+            position: stone,
+            duration: 0.1,
+        });
+        world.commands().queue(SpawnPowerUp {
+            position: Vec2::ZERO,
+            power_up: PowerUp::Laser,
+        });
+        world.flush();
+        original.update();
+        original.world_mut().run_system_cached(save_game).unwrap();
+        let mut continued = continue_saved_game_with(&path, |app| {
+            app.insert_resource(Recorder::new(replay_path.clone()));
+        });
+
+        original.update();
+        let expected = drops_and_fuses(&mut original);
+        assert_eq!((expected.0.len(), expected.1.len()), (1, 1));
+        assert_eq!(drops_and_fuses(&mut continued), expected);
+
+        // The explosion destroys the same stones in both games
+        for _ in 0..10 {
+            original.update();
+            continued.update();
+        }
+        let expected = snapshot(&mut original);
+        assert!(expected.3 > 0);
+        assert_eq!(snapshot(&mut continued), expected);
+
+        // The continued game cannot be replayed, so it is not recorded
+        if replay_path.exists() {
+            fs::remove_file(&replay_path).unwrap();
+        }
+        continued
+            .world_mut()
+            .resource_mut::<NextState<GameState>>()
+            .set(GameState::Title);
+        continued.update();
+        assert!(!replay_path.exists());
+    }
+}
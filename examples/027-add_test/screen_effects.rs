@@ -0,0 +1,191 @@
+use bevy::{input::common_conditions::input_just_pressed, prelude::*};
+
+use crate::{CollisionMessage, Combo, GameSet, GameState, Obstacle, StoneDestroyed};
+
+const MAX_SHAKE_OFFSET: f32 = 24.0;
+const MAX_SHAKE_ANGLE: f32 = 0.03;
+// Trauma lost per second
+const TRAUMA_DECAY: f32 = 1.5;
+// Hitting a stone shakes more than bouncing off a wall, even if the stone is not destroyed
+const WALL_TRAUMA: f32 = 0.1;
+const STONE_TRAUMA: f32 = 0.25;
+// Added to the trauma of the hit, so that destroying a stone shakes the most
+const DESTROY_TRAUMA: f32 = 0.2;
+// Every time the combo reaches a multiple of this value, the game freezes briefly
+const HIT_STOP_COMBO: u32 = 5;
+const HIT_STOP_DURATION: f32 = 0.08;
+
+pub struct ScreenEffectsPlugin;
+
+impl Plugin for ScreenEffectsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(MotionSettings {
+            reduce_motion: std::env::args().any(|arg| arg == "--reduce-motion"),
+        })
+        .init_resource::<HitStop>()
+        .add_observer(add_destroy_trauma)
+        .add_systems(
+            Update,
+            (
+                // The demo on the title screen does not shake the camera
+                add_trauma.run_if(in_state(GameState::Game)),
+                shake_camera,
+                start_hit_stop,
+                end_hit_stop,
+                toggle_reduced_motion.run_if(input_just_pressed(KeyCode::KeyM)),
+            )
+                .in_set(GameSet::Feedback),
+        );
+    }
+}
+
+// Disables screen shake and hit-stop for motion-sensitive players.
+// Can be toggled with `M` or enabled from the start with `--reduce-motion`.
+#[derive(Resource)]
+pub struct MotionSettings {
+    pub reduce_motion: bool,
+}
+
+// Added to the camera. The shake grows with the square of `trauma`, so that small hits stay subtle.
+#[derive(Component, Default)]
+pub struct ScreenShake {
+    trauma: f32,
+}
+
+#[derive(Default, Resource)]
+struct HitStop(Option<Timer>);
+
+fn add_trauma(
+    mut collision_messages: MessageReader<CollisionMessage>,
+    mut shakes: Query<&mut ScreenShake>,
+    settings: Res<MotionSettings>,
+) {
+    for message in collision_messages.read() {
+        if settings.reduce_motion {
+            continue;
+        }
+
+        let trauma = match message.obstacle {
+            Obstacle::Stone => STONE_TRAUMA,
+            Obstacle::Wall | Obstacle::SafetyNet | Obstacle::Switch => WALL_TRAUMA,
+            Obstacle::Bat => 0.0,
+        };
+
+        for mut shake in &mut shakes {
+            shake.trauma = (shake.trauma + trauma).min(1.0);
+        }
+    }
+}
+
+// Also for the stones destroyed by an explosion, which are not hit by the ball
+fn add_destroy_trauma(
+    _destroyed: On<StoneDestroyed>,
+    game_state: Res<State<GameState>>,
+    mut shakes: Query<&mut ScreenShake>,
+    settings: Res<MotionSettings>,
+) {
+    if *game_state != GameState::Game || settings.reduce_motion {
+        return;
+    }
+
+    for mut shake in &mut shakes {
+        shake.trauma = (shake.trauma + DESTROY_TRAUMA).min(1.0);
+    }
+}
+
+// Uses the real time, so that the camera keeps shaking during a hit-stop
+fn shake_camera(time: Res<Time<Real>>, mut cameras: Query<(&mut ScreenShake, &mut Transform)>) {
+    let elapsed = time.elapsed_secs();
+
+    for (mut shake, mut transform) in &mut cameras {
+        let strength = shake.trauma * shake.trauma;
+
+        transform.translation.x = MAX_SHAKE_OFFSET * strength * noise(elapsed, 1.0);
+        transform.translation.y = MAX_SHAKE_OFFSET * strength * noise(elapsed, 2.0);
+        transform.rotation =
+            Quat::from_rotation_z(MAX_SHAKE_ANGLE * strength * noise(elapsed, 3.0));
+
+        shake.trauma = (shake.trauma - TRAUMA_DECAY * time.delta_secs()).max(0.0);
+    }
+}
+
+// A cheap, smooth value between -1 and 1 that differs for each `seed`
+fn noise(time: f32, seed: f32) -> f32 {
+    ((time * 23.0 + seed * 17.0).sin() + (time * 37.0 + seed * 5.0).sin() * 0.5) / 1.5
+}
+
+fn start_hit_stop(
+    combo: Res<Combo>,
+    settings: Res<MotionSettings>,
+    mut last_combo: Local<u32>,
+    mut hit_stop: ResMut<HitStop>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    // Several stones can be hit in one frame, so check whether a multiple was passed
+    let reached_milestone = combo.0 / HIT_STOP_COMBO > *last_combo / HIT_STOP_COMBO;
+    *last_combo = combo.0;
+
+    if reached_milestone && !settings.reduce_motion {
+        time.pause();
+        hit_stop.0 = Some(Timer::from_seconds(HIT_STOP_DURATION, TimerMode::Once));
+    }
+}
+
+fn end_hit_stop(
+    real_time: Res<Time<Real>>,
+    mut hit_stop: ResMut<HitStop>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    if let Some(timer) = &mut hit_stop.0 {
+        timer.tick(real_time.delta());
+        if timer.is_finished() {
+            time.unpause();
+            hit_stop.0 = None;
+        }
+    }
+}
+
+fn toggle_reduced_motion(mut settings: ResMut<MotionSettings>) {
+    settings.reduce_motion = !settings.reduce_motion;
+    info!("Reduced motion: {}", settings.reduce_motion);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testing::test_app, Collision};
+
+    // The trauma of the camera after a single hit of the obstacle
+    fn trauma_after_hit(obstacle: Obstacle) -> f32 {
+        let mut app = test_app();
+
+        app.insert_resource(MotionSettings {
+            reduce_motion: false,
+        })
+        .add_systems(Update, add_trauma);
+        let camera = app.world_mut().spawn(ScreenShake::default()).id();
+        let ball = app.world_mut().spawn_empty().id();
+        app.world_mut().write_message(CollisionMessage {
+            ball,
+            obstacle_entity: ball,
+            obstacle,
+            point: Vec2::ZERO,
+            side: Collision::Bottom,
+            impact_speed: 0.0,
+        });
+
+        app.update();
+
+        app.world().get::<ScreenShake>(camera).unwrap().trauma
+    }
+
+    #[test]
+    fn test_stones_shake_more_than_walls() {
+        let stone = trauma_after_hit(Obstacle::Stone);
+        let wall = trauma_after_hit(Obstacle::Wall);
+
+        assert!(stone > wall);
+        assert!(wall > 0.0);
+        assert_eq!(trauma_after_hit(Obstacle::Bat), 0.0);
+    }
+}
@@ -0,0 +1,174 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+
+use crate::{
+    check_for_collisions, rng::GameRng, Ball, CollisionMessage, GameSet, Obstacle, Playing,
+};
+
+// The flattest angle to the horizontal at which the ball may leave an obstacle
+const MIN_VERTICAL_ANGLE: f32 = 15.0 * PI / 180.0;
+// A ball that touches neither the bat nor a stone for this long is stuck in a loop
+const STALL_TICKS: u32 = 60 * 10;
+// The range of angles by which a stuck ball is turned, to the left or to the right
+const NUDGE_ANGLES: std::ops::Range<f32> = (10.0 * PI / 180.0)..(25.0 * PI / 180.0);
+
+// Keeps the ball from bouncing between walls and barriers forever
+pub struct AntiStallPlugin;
+
+impl Plugin for AntiStallPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            nudge_stalled_balls
+                .in_set(GameSet::Resolve)
+                .after(check_for_collisions)
+                .run_if(in_state(Playing)),
+        );
+    }
+}
+
+// The number of ticks since the ball last touched the bat or a stone
+#[derive(Component, Default)]
+pub struct Stall {
+    ticks: u32,
+}
+
+// Turns `velocity` up or down until it is at least `MIN_VERTICAL_ANGLE` steep, keeping its speed
+pub fn with_min_vertical_angle(velocity: Vec2) -> Vec2 {
+    let speed = velocity.length();
+    let min_y = speed * MIN_VERTICAL_ANGLE.sin();
+    if velocity.y.abs() >= min_y {
+        return velocity;
+    }
+
+    Vec2::new(
+        velocity.x.signum() * speed * MIN_VERTICAL_ANGLE.cos(),
+        velocity.y.signum() * min_y,
+    )
+}
+
+fn nudge_stalled_balls(
+    mut collision_messages: MessageReader<CollisionMessage>,
+    mut balls: Query<(Entity, &mut Ball, &mut Stall)>,
+    mut rng: ResMut<GameRng>,
+) {
+    let progress: Vec<Entity> = collision_messages
+        .read()
+        .filter(|message| matches!(message.obstacle, Obstacle::Bat | Obstacle::Stone))
+        .map(|message| message.ball)
+        .collect();
+
+    for (entity, mut ball, mut stall) in &mut balls {
+        if progress.contains(&entity) {
+            stall.ticks = 0;
+            continue;
+        }
+
+        stall.ticks += 1;
+        if stall.ticks >= STALL_TICKS {
+            let angle = rng.range(NUDGE_ANGLES);
+            let angle = if rng.chance(0.5) { angle } else { -angle };
+            ball.velocity = with_min_vertical_angle(Vec2::from_angle(angle).rotate(ball.velocity));
+            stall.ticks = 0;
+
+            debug!("Nudged ball {entity} out of a loop");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::bounding::{Aabb2d, BoundingCircle};
+
+    use super::*;
+    use crate::{
+        apply_velocity, ball_collision,
+        testing::{spawn_wall, test_app},
+        Collision, BALL_RADIUS, BALL_SPEED,
+    };
+
+    #[test]
+    fn test_reflection_keeps_minimum_vertical_angle() {
+        let mut app = test_app();
+
+        app.add_systems(
+            Update,
+            (apply_velocity, check_for_collisions, nudge_stalled_balls).chain(),
+        );
+        let wall = (Vec2::new(100.0, 0.0), Vec2::new(20.0, 400.0));
+        spawn_wall(app.world_mut(), wall.0, wall.1);
+
+        // The ball flies almost horizontally into the left side of the wall
+        let position = Vec2::new(wall.0.x - wall.1.x / 2.0 - BALL_RADIUS + 1.0, 0.0);
+        assert_eq!(
+            ball_collision(
+                BoundingCircle::new(position, BALL_RADIUS),
+                Aabb2d::new(wall.0, wall.1 / 2.0)
+            ),
+            Some(Collision::Left)
+        );
+        let ball = app
+            .world_mut()
+            .spawn((
+                Transform::from_translation(position.extend(0.0)),
+                Ball {
+                    velocity: Vec2::from_angle(0.02) * BALL_SPEED,
+                },
+                Stall::default(),
+            ))
+            .id();
+
+        app.update();
+
+        let velocity = app.world().get::<Ball>(ball).unwrap().velocity;
+        assert!(velocity.x < 0.0);
+        assert!(velocity.y >= BALL_SPEED * MIN_VERTICAL_ANGLE.sin() - 0.01);
+        assert!((velocity.length() - BALL_SPEED).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_ball_bouncing_between_walls_is_nudged() {
+        let mut app = test_app();
+
+        app.add_systems(
+            Update,
+            (apply_velocity, check_for_collisions, nudge_stalled_balls).chain(),
+        );
+        // The ball bounces straight up and down between two walls, without bat or stones
+        let top = (Vec2::new(0.0, 100.0), Vec2::new(400.0, 20.0));
+        let bottom = (Vec2::new(0.0, -100.0), Vec2::new(400.0, 20.0));
+        for (position, size) in [top, bottom] {
+            spawn_wall(app.world_mut(), position, size);
+        }
+
+        let velocity = Vec2::new(0.0, BALL_SPEED);
+        let ball = app
+            .world_mut()
+            .spawn((Transform::default(), Ball { velocity }, Stall::default()))
+            .id();
+
+        let mut touched_walls = 0;
+        for _ in 0..STALL_TICKS - 1 {
+            app.update();
+
+            let position = app.world().get::<Transform>(ball).unwrap().translation;
+            let bounds = BoundingCircle::new(position.truncate(), BALL_RADIUS + 1.0);
+            touched_walls += [top, bottom]
+                .iter()
+                .filter(|(center, size)| {
+                    ball_collision(bounds, Aabb2d::new(*center, *size / 2.0)).is_some()
+                })
+                .count();
+        }
+        assert!(touched_walls > 1);
+        assert_eq!(app.world().get::<Ball>(ball).unwrap().velocity.x, 0.0);
+
+        app.update();
+
+        let nudged = app.world().get::<Ball>(ball).unwrap().velocity;
+        assert!(nudged.x.abs() > BALL_SPEED * NUDGE_ANGLES.start.sin() - 0.01);
+        assert!((nudged.length() - BALL_SPEED).abs() < 0.01);
+        assert_eq!(app.world().get::<Stall>(ball).unwrap().ticks, 0);
+    }
+}
@@ -0,0 +1,241 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+
+use crate::{
+    apply_velocity, check_for_collisions, move_bat, Ball, BatInput, CollisionMessage, GameSet,
+    GameState, Obstacle, BALL_RADIUS, BAT_SIZE,
+};
+
+const STICKY_DURATION: f32 = 15.0;
+// A stuck ball is released by itself if the player does not click in time
+const RELEASE_TIMEOUT: f32 = 3.0;
+// The angle to the vertical at which a ball stuck to the end of the bat is released
+const MAX_RELEASE_ANGLE: f32 = 60.0 * PI / 180.0;
+
+// A bat with the sticky power-up catches the ball and releases it with a click, in a direction
+// that depends on where the ball was caught, like the bounce off the bat in the original game
+pub struct StickyBatPlugin;
+
+impl Plugin for StickyBatPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (
+                release_stuck_balls.in_set(GameSet::Input).after(move_bat),
+                carry_stuck_balls
+                    .in_set(GameSet::Physics)
+                    .after(apply_velocity),
+                stick_balls
+                    .in_set(GameSet::Resolve)
+                    .after(check_for_collisions),
+                wear_out_sticky_bats.in_set(GameSet::Cleanup),
+            )
+                .run_if(in_state(GameState::Game)),
+        );
+    }
+}
+
+#[derive(Clone, Component, Reflect)]
+#[reflect(Component)]
+pub struct Sticky {
+    pub timer: Timer,
+}
+
+impl Default for Sticky {
+    fn default() -> Self {
+        Self {
+            timer: Timer::from_seconds(STICKY_DURATION, TimerMode::Once),
+        }
+    }
+}
+
+// A ball that rests on top of the bat. Its velocity is already the one it is released with.
+#[derive(Clone, Component, Reflect)]
+#[reflect(Component)]
+pub struct Stuck {
+    #[entities]
+    pub bat: Entity,
+    // The horizontal distance from the center of the bat
+    pub offset: f32,
+    pub timer: Timer,
+}
+
+// Straight up from the center of the bat, and more to the side the farther out the ball is
+fn release_velocity(offset: f32, speed: f32) -> Vec2 {
+    let angle = offset / (BAT_SIZE.x / 2.0) * MAX_RELEASE_ANGLE;
+    Vec2::new(angle.sin(), angle.cos()) * speed
+}
+
+fn stick_balls(
+    mut commands: Commands,
+    mut collision_messages: MessageReader<CollisionMessage>,
+    mut balls: Query<(&mut Ball, &Transform), Without<Stuck>>,
+    bats: Query<&Transform, With<Sticky>>,
+) {
+    for message in collision_messages.read() {
+        if !matches!(message.obstacle, Obstacle::Bat) {
+            continue;
+        }
+        let (Ok((mut ball, transform)), Ok(bat)) = (
+            balls.get_mut(message.ball),
+            bats.get(message.obstacle_entity),
+        ) else {
+            continue;
+        };
+
+        let offset = (transform.translation.x - bat.translation.x)
+            .clamp(-BAT_SIZE.x / 2.0, BAT_SIZE.x / 2.0);
+        ball.velocity = release_velocity(offset, ball.velocity.length());
+        commands.entity(message.ball).insert(Stuck {
+            bat: message.obstacle_entity,
+            offset,
+            timer: Timer::from_seconds(RELEASE_TIMEOUT, TimerMode::Once),
+        });
+    }
+}
+
+// All stuck balls are released with the same click
+fn release_stuck_balls(
+    mut commands: Commands,
+    time: Res<Time>,
+    bat_input: Res<BatInput>,
+    mut balls: Query<(Entity, &mut Stuck)>,
+) {
+    for (entity, mut stuck) in &mut balls {
+        stuck.timer.tick(time.delta());
+        if bat_input.click || stuck.timer.is_finished() {
+            commands.entity(entity).remove::<Stuck>();
+        }
+    }
+}
+
+// Puts the stuck balls back on top of the bat, after they moved like any other ball.
+// They stay just above it, so that they do not collide with it again.
+fn carry_stuck_balls(
+    mut commands: Commands,
+    mut balls: Query<(Entity, &Stuck, &mut Transform)>,
+    bats: Query<&Transform, Without<Stuck>>,
+) {
+    for (entity, stuck, mut transform) in &mut balls {
+        let Ok(bat) = bats.get(stuck.bat) else {
+            commands.entity(entity).remove::<Stuck>();
+            continue;
+        };
+
+        transform.translation.x = bat.translation.x + stuck.offset;
+        transform.translation.y = bat.translation.y + BAT_SIZE.y / 2.0 + BALL_RADIUS + 1.0;
+    }
+}
+
+fn wear_out_sticky_bats(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut bats: Query<(Entity, &mut Sticky)>,
+) {
+    for (entity, mut sticky) in &mut bats {
+        sticky.timer.tick(time.delta());
+        if sticky.timer.is_finished() {
+            commands.entity(entity).remove::<Sticky>();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        testing::{click, drop_ball, spawn_bat, test_app},
+        BALL_SPEED,
+    };
+
+    #[test]
+    fn test_stuck_ball_follows_the_bat_until_released() {
+        let mut app = test_app();
+
+        app.add_systems(
+            Update,
+            (
+                move_bat,
+                release_stuck_balls,
+                apply_velocity,
+                carry_stuck_balls,
+                check_for_collisions,
+                stick_balls,
+            )
+                .chain(),
+        );
+        let bat = spawn_bat(&mut app);
+        app.world_mut().entity_mut(bat).insert(Sticky::default());
+        let ball = drop_ball(app.world_mut(), bat, BAT_SIZE.x / 4.0);
+
+        for _ in 0..5 {
+            app.update();
+        }
+        assert!(app.world().get::<Stuck>(ball).is_some());
+
+        // The ball is carried along with the bat
+        app.world_mut().resource_mut::<BatInput>().movement = 100.0;
+        app.update();
+        app.world_mut().resource_mut::<BatInput>().movement = 0.0;
+        app.update();
+        let bat_x = app.world().get::<Transform>(bat).unwrap().translation.x;
+        let ball_x = app.world().get::<Transform>(ball).unwrap().translation.x;
+        assert_eq!(bat_x, 100.0);
+        assert_eq!(ball_x, bat_x + BAT_SIZE.x / 4.0);
+        let stuck_y = app.world().get::<Transform>(ball).unwrap().translation.y;
+
+        // Released to the right, at half the maximum angle
+        click(&mut app);
+        for _ in 0..5 {
+            app.update();
+        }
+        assert!(app.world().get::<Stuck>(ball).is_none());
+        let velocity = app.world().get::<Ball>(ball).unwrap().velocity;
+        assert!((velocity - release_velocity(BAT_SIZE.x / 4.0, BALL_SPEED)).length() < 0.01);
+        assert!((velocity.angle_to(Vec2::Y) - MAX_RELEASE_ANGLE / 2.0).abs() < 0.01);
+        assert!(app.world().get::<Transform>(ball).unwrap().translation.y > stuck_y);
+    }
+
+    #[test]
+    fn test_stuck_balls_are_released_after_timeout() {
+        let mut app = test_app();
+
+        app.add_systems(
+            Update,
+            (
+                release_stuck_balls,
+                apply_velocity,
+                carry_stuck_balls,
+                check_for_collisions,
+                stick_balls,
+            )
+                .chain(),
+        );
+        let bat = spawn_bat(&mut app);
+        app.world_mut().entity_mut(bat).insert(Sticky::default());
+        let balls = [
+            drop_ball(app.world_mut(), bat, -BAT_SIZE.x / 2.0),
+            drop_ball(app.world_mut(), bat, 0.0),
+        ];
+
+        for _ in 0..5 {
+            app.update();
+        }
+        for ball in balls {
+            assert!(app.world().get::<Stuck>(ball).is_some());
+        }
+
+        // 16 ms per update
+        for _ in 0..(RELEASE_TIMEOUT * 1000.0 / 16.0) as usize {
+            app.update();
+        }
+
+        let velocities = balls.map(|ball| app.world().get::<Ball>(ball).unwrap().velocity);
+        for ball in balls {
+            assert!(app.world().get::<Stuck>(ball).is_none());
+        }
+        assert!(velocities[0].x < 0.0 && velocities[0].y > 0.0);
+        assert_eq!(velocities[1], Vec2::new(0.0, BALL_SPEED));
+    }
+}
@@ -0,0 +1,93 @@
+use std::time::Duration;
+
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+
+use crate::{
+    rng::GameRng, Ball, Bat, BatInput, Collider, CollisionMessage, Combo, GameState, Obstacle,
+    Score, SpawnBat, BALL_RADIUS, BALL_SPEED, BAT_SIZE, STONE_SIZE,
+};
+
+// Creates an app for the tests of single systems, which the tests add themselves.
+// It has the resources and messages those systems rely on, is already in `GameState::Game`,
+// and every update advances the time by 16 ms.
+pub fn test_app() -> App {
+    let mut app = App::new();
+
+    app.add_plugins(MinimalPlugins)
+        .init_resource::<Score>()
+        .init_resource::<Combo>()
+        .init_resource::<BatInput>()
+        .init_resource::<GameRng>()
+        .insert_resource(State::new(GameState::Game))
+        .add_message::<CollisionMessage>()
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+            16,
+        )));
+
+    app
+}
+
+// A bat in the middle of the screen, controlled by `BatInput`
+pub fn spawn_bat(app: &mut App) -> Entity {
+    app.world_mut().commands().queue(SpawnBat {
+        x: 0.0,
+        autopilot: None,
+    });
+    app.world_mut().flush();
+
+    let world = app.world_mut();
+    world
+        .query_filtered::<Entity, With<Bat>>()
+        .single(world)
+        .unwrap()
+}
+
+pub fn spawn_wall(world: &mut World, position: Vec2, size: Vec2) -> Entity {
+    world
+        .spawn((
+            Transform::from_translation(position.extend(0.0)),
+            Collider {
+                size: Some(size),
+                obstacle: Obstacle::Wall,
+            },
+        ))
+        .id()
+}
+
+// A ball that moves up into the bottom side of an obstacle of `STONE_SIZE` at `position`
+pub fn spawn_ball_below(world: &mut World, position: Vec2) -> Entity {
+    world
+        .spawn((
+            Transform::from_translation(
+                (position - Vec2::Y * (STONE_SIZE.y / 2.0 + BALL_RADIUS - 1.0)).extend(0.0),
+            ),
+            Ball {
+                velocity: Vec2::new(0.0, BALL_SPEED),
+            },
+        ))
+        .id()
+}
+
+// A ball that falls straight down onto the bat, at `offset` from its center
+pub fn drop_ball(world: &mut World, bat: Entity, offset: f32) -> Entity {
+    let bat = world.get::<Transform>(bat).unwrap().translation;
+    world
+        .spawn((
+            Transform::from_xyz(
+                bat.x + offset,
+                bat.y + BAT_SIZE.y / 2.0 + BALL_RADIUS + 5.0,
+                0.0,
+            ),
+            Ball {
+                velocity: Vec2::new(0.0, -BALL_SPEED),
+            },
+        ))
+        .id()
+}
+
+// Clicks during a single update
+pub fn click(app: &mut App) {
+    app.world_mut().resource_mut::<BatInput>().click = true;
+    app.update();
+    app.world_mut().resource_mut::<BatInput>().click = false;
+}
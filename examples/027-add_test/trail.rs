@@ -0,0 +1,135 @@
+use bevy::prelude::*;
+
+use crate::{Ball, GameSet, OnGameScreen, Playing, BALL_SPEED};
+
+// How long a trail segment lives when the ball moves at `BALL_SPEED`
+const TRAIL_DURATION: f32 = 0.12;
+const TRAIL_ALPHA: f32 = 0.4;
+
+pub struct TrailPlugin;
+
+impl Plugin for TrailPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            // One segment per tick, so that the spacing of the segments does not depend on the
+            // frame rate, and none pile up during a hit-stop, which pauses the fixed timestep
+            FixedUpdate,
+            spawn_trail
+                .in_set(GameSet::Feedback)
+                .run_if(in_state(Playing)),
+        )
+        .add_systems(
+            Update,
+            fade_trail
+                .in_set(GameSet::Feedback)
+                .run_if(in_state(Playing)),
+        );
+    }
+}
+
+#[derive(Component)]
+struct TrailSegment {
+    ball: Entity,
+    lifetime: Timer,
+}
+
+// Faster balls leave longer trails, because their segments live longer
+fn spawn_trail(
+    mut commands: Commands,
+    balls: Query<(Entity, &Ball, &Transform, &Sprite, &Visibility)>,
+) {
+    for (entity, ball, transform, sprite, visibility) in &balls {
+        let duration = TRAIL_DURATION * ball.velocity.length() / BALL_SPEED;
+        if *visibility == Visibility::Hidden || duration <= 0.0 {
+            continue;
+        }
+
+        commands.spawn((
+            Sprite {
+                color: sprite.color.with_alpha(TRAIL_ALPHA),
+                ..sprite.clone()
+            },
+            Transform::from_translation(transform.translation.with_z(-0.5)),
+            TrailSegment {
+                ball: entity,
+                lifetime: Timer::from_seconds(duration, TimerMode::Once),
+            },
+            OnGameScreen,
+        ));
+    }
+}
+
+fn fade_trail(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut segments: Query<(
+        Entity,
+        &mut TrailSegment,
+        &mut Sprite,
+        &mut Transform,
+        &mut Visibility,
+    )>,
+    balls: Query<&Visibility, (With<Ball>, Without<TrailSegment>)>,
+) {
+    for (entity, mut segment, mut sprite, mut transform, mut visibility) in &mut segments {
+        segment.lifetime.tick(time.delta());
+        if segment.lifetime.is_finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        let remaining = segment.lifetime.fraction_remaining();
+        sprite.color.set_alpha(TRAIL_ALPHA * remaining);
+        transform.scale = Vec3::splat(remaining);
+
+        // Hiding a ball hides its trail as well
+        if let Ok(ball_visibility) = balls.get(segment.ball) {
+            *visibility = *ball_visibility;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::time::TimeUpdateStrategy;
+
+    use super::*;
+    use crate::headless::headless_app_with;
+
+    fn segments(app: &mut App) -> usize {
+        let world = app.world_mut();
+        world.query::<&TrailSegment>().iter(world).count()
+    }
+
+    fn ticks(app: &App) -> f64 {
+        let time = app.world().resource::<Time<Fixed>>();
+        time.elapsed().div_duration_f64(time.timestep())
+    }
+
+    #[test]
+    fn test_trail_does_not_depend_on_frame_rate() {
+        let mut app = headless_app_with(|app| {
+            app.add_plugins(TrailPlugin);
+        });
+        // Four frames per tick, which are short enough for all segments to last until the end
+        let timestep = app.world().resource::<Time<Fixed>>().timestep();
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(timestep / 4));
+        let world = app.world_mut();
+        let old_segments: Vec<_> = world
+            .query_filtered::<Entity, With<TrailSegment>>()
+            .iter(world)
+            .collect();
+        for segment in old_segments {
+            world.despawn(segment);
+        }
+        let ticks_before = ticks(&app);
+
+        for _ in 0..12 {
+            app.update();
+        }
+
+        let ticks = (ticks(&app) - ticks_before).round() as usize;
+        assert!(ticks > 0);
+        assert_eq!(segments(&mut app), ticks);
+    }
+}